@@ -69,8 +69,9 @@ fn main() {
 
                 let (w, h) = (win.width as u32, win.height as u32);
 
-                pip.resize(w, h);
-                chain = r.swap_chain(w, h, PresentMode::default());
+                if let Some((w, h)) = chain.resize(&r.device, w, h) {
+                    pip.resize(w, h);
+                }
             }
             _ => {}
         },
@@ -129,7 +130,7 @@ fn main() {
                 let pass = &mut frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &out);
 
                 pass.set_pipeline(&pip);
-                pass.draw_buffer(&buffer);
+                pass.draw_mesh(&buffer);
             }
             r.submit(frame);
         }
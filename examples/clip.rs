@@ -0,0 +1,135 @@
+#![deny(clippy::all)]
+#![allow(clippy::unreadable_literal)]
+#![allow(clippy::single_match)]
+
+use rgx::core::*;
+use rgx::kit::clip::{Clip, Clipped};
+use rgx::kit::shape2d;
+use rgx::kit::shape2d::{Batch, Fill, Line, Shape, Stroke};
+use rgx::math::*;
+
+use raw_window_handle::HasRawWindowHandle;
+use winit::{
+    event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::Window,
+};
+
+fn main() {
+    env_logger::init();
+
+    let event_loop = EventLoop::new();
+    let window = Window::new(&event_loop).unwrap();
+
+    ///////////////////////////////////////////////////////////////////////////
+    // Setup renderer
+    ///////////////////////////////////////////////////////////////////////////
+
+    let mut r = Renderer::new(window.raw_window_handle());
+    let size = window.inner_size().to_physical(window.hidpi_factor());
+    let (mut w, mut h) = (size.width as u32, size.height as u32);
+
+    let mut pipeline: Clipped<shape2d::Pipeline> = r.pipeline(w, h, Blending::default());
+    let mut clip = Clip::new(&r, w, h);
+
+    let mut chain = r.swap_chain(w, h, PresentMode::default());
+    let mut depth = r.depth_buffer_with(w, h, Clip::FORMAT, 1);
+
+    event_loop.run(move |event, _, control_flow| match event {
+        Event::WindowEvent { event, .. } => match event {
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::Escape),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                *control_flow = ControlFlow::Exit;
+            }
+            WindowEvent::CloseRequested => {
+                *control_flow = ControlFlow::Exit;
+            }
+            WindowEvent::Resized(size) => {
+                let size = size.to_physical(window.hidpi_factor());
+                w = size.width as u32;
+                h = size.height as u32;
+
+                if let Some((w, h)) = chain.resize(&r.device, w, h) {
+                    pipeline.resize(w, h);
+                    clip.resize(w, h);
+                }
+                depth = r.depth_buffer_with(w, h, Clip::FORMAT, 1);
+            }
+            _ => {}
+        },
+        Event::EventsCleared => {
+            *control_flow = ControlFlow::Wait;
+
+            let (cx, cy) = (w as f32 / 2., h as f32 / 2.);
+
+            ///////////////////////////////////////////////////////////////////
+            // Prepare masks and contents
+            ///////////////////////////////////////////////////////////////////
+
+            let panel = Batch::singleton(Shape::Rectangle(
+                Rect::new(cx - 200., cy - 150., cx + 200., cy + 150.),
+                Stroke::NONE,
+                Fill::Solid(Rgba::WHITE),
+            ))
+            .finish(&r);
+            let circle = Batch::singleton(Shape::Circle(
+                Point2::new(cx + 200., cy),
+                150.,
+                64,
+                Stroke::NONE,
+                Fill::Solid(Rgba::WHITE),
+            ))
+            .finish(&r);
+
+            let mut batch = Batch::new();
+            for i in 0..(w / 16) {
+                let x = i as f32 * 16.;
+
+                batch.add(Shape::Line(
+                    Line::new(x, 0., x + h as f32, h as f32),
+                    Stroke::new(4.0, Rgba::new(1.0, 0.0, i as f32 / (w / 16) as f32, 1.0)),
+                ));
+            }
+            let lines = batch.finish(&r);
+
+            ///////////////////////////////////////////////////////////////////
+            // Draw frame
+            ///////////////////////////////////////////////////////////////////
+
+            let mut frame = r.frame();
+            r.update_pipeline(&pipeline, Matrix4::identity(), &mut frame);
+            clip.update(&mut r, Matrix4::identity(), &mut frame);
+
+            let out = chain.next();
+            {
+                let pass = &mut frame.pass_with_depth(
+                    PassOp::Clear(Rgba::BLACK),
+                    &out,
+                    &depth,
+                    DepthStencilOp::CLEAR,
+                );
+
+                // Lines are only drawn inside the panel, and the panel is
+                // filled where it overlaps with the circle.
+                clip.with(pass, &panel, |pass| {
+                    pass.set_pipeline(&pipeline);
+                    pass.draw_mesh(&lines);
+
+                    clip.with(pass, &circle, |pass| {
+                        pass.set_pipeline(&pipeline);
+                        pass.draw_mesh(&panel);
+                    });
+                });
+            }
+            r.submit(frame);
+        }
+        _ => {}
+    });
+}
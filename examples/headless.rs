@@ -0,0 +1,68 @@
+#![deny(clippy::all)]
+#![allow(clippy::unreadable_literal)]
+
+use rgx::core::*;
+use rgx::kit::shape2d;
+use rgx::kit::shape2d::{Fill, Shape, Stroke};
+use rgx::math::*;
+
+use image::png::PNGEncoder;
+use image::ColorType;
+
+use std::fs::File;
+
+fn main() {
+    env_logger::init();
+
+    ///////////////////////////////////////////////////////////////////////////
+    // Setup renderer, without a window
+    ///////////////////////////////////////////////////////////////////////////
+
+    let mut r = Renderer::headless();
+    let (w, h) = (256, 256);
+
+    let framebuffer = r.framebuffer(w, h);
+    let pipeline: shape2d::Pipeline = r.pipeline(w, h, Blending::default());
+
+    let buffer = shape2d::Batch::singleton(Shape::Circle(
+        Point2::new(w as f32 / 2., h as f32 / 2.),
+        h as f32 / 3.,
+        64,
+        Stroke::new(3.0, Rgba::new(1.0, 0.0, 1.0, 1.0)),
+        Fill::Solid(Rgba::WHITE),
+    ))
+    .finish(&r);
+
+    ///////////////////////////////////////////////////////////////////////////
+    // Draw frame to the framebuffer
+    ///////////////////////////////////////////////////////////////////////////
+
+    let mut frame = r.frame();
+
+    r.update_pipeline(&pipeline, Matrix4::identity(), &mut frame);
+
+    {
+        let pass = &mut frame.pass(PassOp::Clear(Rgba::BLACK), &framebuffer);
+        pass.set_pipeline(&pipeline);
+        pass.draw_mesh(&buffer);
+    }
+    r.submit(frame);
+
+    ///////////////////////////////////////////////////////////////////////////
+    // Read the framebuffer into host memory and write it to an image file
+    ///////////////////////////////////////////////////////////////////////////
+
+    let data = r
+        .read_texture(
+            &framebuffer.texture,
+            Rect::origin(w, h),
+            Conversion::BgraToRgba,
+        )
+        .and_then(|readback| readback.wait(&r.device))
+        .unwrap();
+
+    let file = File::create("headless.png").unwrap();
+    let png = PNGEncoder::new(file);
+
+    png.encode(&data, w, h, ColorType::RGBA(8)).unwrap();
+}
@@ -0,0 +1,88 @@
+#![deny(clippy::all)]
+#![allow(clippy::unreadable_literal)]
+#![allow(clippy::single_match)]
+
+use rgx::core::*;
+use rgx::kit::shape2d;
+use rgx::kit::shape2d::{Fill, Shape, Stroke};
+use rgx::math::*;
+
+use raw_window_handle::HasRawWindowHandle;
+use winit::{
+    event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::Window,
+};
+
+fn main() {
+    env_logger::init();
+
+    let event_loop = EventLoop::new();
+    let window = Window::new(&event_loop).unwrap();
+    let palette = Window::new(&event_loop).unwrap();
+
+    ///////////////////////////////////////////////////////////////////////////
+    // Setup renderer, with one surface per window
+    ///////////////////////////////////////////////////////////////////////////
+
+    let mut r = Renderer::new(window.raw_window_handle());
+    let surface = r.surface(palette.raw_window_handle());
+
+    let size = window.inner_size().to_physical(window.hidpi_factor());
+    let (w, h) = (size.width as u32, size.height as u32);
+
+    let size = palette.inner_size().to_physical(palette.hidpi_factor());
+    let (pw, ph) = (size.width as u32, size.height as u32);
+
+    // The pipeline and vertex buffer are shared between both windows.
+    let pipeline: shape2d::Pipeline = r.pipeline(w, h, Blending::default());
+    let buffer = shape2d::Batch::singleton(Shape::Circle(
+        Point2::new(w as f32 / 2., h as f32 / 2.),
+        h as f32 / 4.,
+        64,
+        Stroke::new(2.0, Rgba::WHITE),
+        Fill::Solid(Rgba::new(1.0, 0.0, 1.0, 1.0)),
+    ))
+    .finish(&r);
+
+    let mut main_chain = r.swap_chain(w, h, PresentMode::default());
+    let mut palette_chain = r.swap_chain_for(&surface, pw, ph, PresentMode::default());
+
+    event_loop.run(move |event, _, control_flow| match event {
+        Event::WindowEvent { event, .. } => match event {
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::Escape),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                *control_flow = ControlFlow::Exit;
+            }
+            WindowEvent::CloseRequested => {
+                *control_flow = ControlFlow::Exit;
+            }
+            _ => {}
+        },
+        Event::EventsCleared => {
+            *control_flow = ControlFlow::Wait;
+
+            let mut frame = r.frame();
+            r.update_pipeline(&pipeline, Matrix4::identity(), &mut frame);
+
+            let main_out = main_chain.next();
+            let palette_out = palette_chain.next();
+
+            for (out, bg) in &[(&main_out, Rgba::BLACK), (&palette_out, Rgba::WHITE)] {
+                let pass = &mut frame.pass(PassOp::Clear(*bg), *out);
+
+                pass.set_pipeline(&pipeline);
+                pass.draw_mesh(&buffer);
+            }
+            r.submit(frame);
+        }
+        _ => {}
+    });
+}
@@ -20,19 +20,28 @@ use winit::{
 
 pub struct Framebuffer {
     target: core::Framebuffer,
-    vertices: core::VertexBuffer,
+    vertices: core::VertexBuffer<Vertex>,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct Vertex(f32, f32, f32, f32);
+
+impl core::Vertex for Vertex {
+    const FORMATS: &'static [core::VertexFormat] =
+        &[core::VertexFormat::Float2, core::VertexFormat::Float2];
 }
 
 impl Framebuffer {
     fn new(w: u32, h: u32, r: &core::Renderer) -> Self {
         #[rustfmt::skip]
-        let vertices: &[(f32, f32, f32, f32)] = &[
-            (-1.0, -1.0, 0.0, 1.0),
-            ( 1.0, -1.0, 1.0, 1.0),
-            ( 1.0,  1.0, 1.0, 0.0),
-            (-1.0, -1.0, 0.0, 1.0),
-            (-1.0,  1.0, 0.0, 0.0),
-            ( 1.0,  1.0, 1.0, 0.0),
+        let vertices: &[Vertex] = &[
+            Vertex(-1.0, -1.0, 0.0, 1.0),
+            Vertex( 1.0, -1.0, 1.0, 1.0),
+            Vertex( 1.0,  1.0, 1.0, 0.0),
+            Vertex(-1.0, -1.0, 0.0, 1.0),
+            Vertex(-1.0,  1.0, 0.0, 0.0),
+            Vertex( 1.0,  1.0, 1.0, 0.0),
         ];
 
         Self {
@@ -56,7 +65,9 @@ impl<'a> core::AbstractPipeline<'a> for FramebufferPipeline {
 
     fn description() -> core::PipelineDescription<'a> {
         core::PipelineDescription {
-            vertex_layout: &[core::VertexFormat::Float2, core::VertexFormat::Float2],
+            vertex_layout: <Vertex as core::Vertex>::FORMATS,
+            vertex_stride: None,
+            vertex_slots: &[],
             pipeline_layout: &[
                 Set(&[Binding {
                     binding: BindingType::UniformBuffer,
@@ -76,6 +87,12 @@ impl<'a> core::AbstractPipeline<'a> for FramebufferPipeline {
             // TODO: Use `env("CARGO_MANIFEST_DIR")`
             vertex_shader: include_bytes!("data/framebuffer.vert.spv"),
             fragment_shader: include_bytes!("data/framebuffer.frag.spv"),
+            format: core::SwapChain::FORMAT,
+            index_format: core::IndexFormat::Uint16,
+            targets: &[],
+            depth_stencil: None,
+            primitive: core::PrimitiveState::default(),
+            entry_points: core::EntryPoints::MAIN,
         }
     }
 
@@ -211,9 +228,10 @@ fn main() {
                 let physical = size.to_physical(window.hidpi_factor());
                 let (w, h) = (physical.width as u32, physical.height as u32);
 
-                offscreen.resize(w, h);
-                onscreen.resize(w, h);
-                textures = r.swap_chain(w, h, PresentMode::default());
+                if let Some((w, h)) = textures.resize(&r.device, w, h) {
+                    offscreen.resize(w, h);
+                    onscreen.resize(w, h);
+                }
             }
             _ => {}
         },
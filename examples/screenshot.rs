@@ -19,19 +19,28 @@ use winit::{event_loop::EventLoop, window::Window};
 
 pub struct Framebuffer {
     target: core::Framebuffer,
-    vertices: core::VertexBuffer,
+    vertices: core::VertexBuffer<Vertex>,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct Vertex(f32, f32, f32, f32);
+
+impl core::Vertex for Vertex {
+    const FORMATS: &'static [core::VertexFormat] =
+        &[core::VertexFormat::Float2, core::VertexFormat::Float2];
 }
 
 impl Framebuffer {
     fn new(w: u32, h: u32, r: &core::Renderer) -> Self {
         #[rustfmt::skip]
-        let vertices: &[(f32, f32, f32, f32)] = &[
-            (-1.0, -1.0, 0.0, 1.0),
-            ( 1.0, -1.0, 1.0, 1.0),
-            ( 1.0,  1.0, 1.0, 0.0),
-            (-1.0, -1.0, 0.0, 1.0),
-            (-1.0,  1.0, 0.0, 0.0),
-            ( 1.0,  1.0, 1.0, 0.0),
+        let vertices: &[Vertex] = &[
+            Vertex(-1.0, -1.0, 0.0, 1.0),
+            Vertex( 1.0, -1.0, 1.0, 1.0),
+            Vertex( 1.0,  1.0, 1.0, 0.0),
+            Vertex(-1.0, -1.0, 0.0, 1.0),
+            Vertex(-1.0,  1.0, 0.0, 0.0),
+            Vertex( 1.0,  1.0, 1.0, 0.0),
         ];
 
         Self {
@@ -55,7 +64,9 @@ impl<'a> core::AbstractPipeline<'a> for FramebufferPipeline {
 
     fn description() -> core::PipelineDescription<'a> {
         core::PipelineDescription {
-            vertex_layout: &[core::VertexFormat::Float2, core::VertexFormat::Float2],
+            vertex_layout: <Vertex as core::Vertex>::FORMATS,
+            vertex_stride: None,
+            vertex_slots: &[],
             pipeline_layout: &[
                 Set(&[Binding {
                     binding: BindingType::UniformBuffer,
@@ -75,6 +86,12 @@ impl<'a> core::AbstractPipeline<'a> for FramebufferPipeline {
             // TODO: Use `env("CARGO_MANIFEST_DIR")`
             vertex_shader: include_bytes!("data/framebuffer.vert.spv"),
             fragment_shader: include_bytes!("data/framebuffer.frag.spv"),
+            format: core::SwapChain::FORMAT,
+            index_format: core::IndexFormat::Uint16,
+            targets: &[],
+            depth_stencil: None,
+            primitive: core::PrimitiveState::default(),
+            entry_points: core::EntryPoints::MAIN,
         }
     }
 
@@ -181,7 +198,7 @@ fn main() {
     {
         let pass = &mut frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &framebuffer.target);
         pass.set_pipeline(&offscreen);
-        pass.draw_buffer(&buffer);
+        pass.draw_mesh(&buffer);
     }
 
     {
@@ -197,13 +214,16 @@ fn main() {
     // Read the framebuffer into host memory and write it to an image file
     ///////////////////////////////////////////////////////////////////////////
 
-    let w = framebuffer.target.texture.w;
-    let h = framebuffer.target.texture.h;
+    let texture = &framebuffer.target.texture;
+    let (w, h) = (texture.w, texture.h);
+
+    let data = r
+        .read_texture(texture, Rect::origin(w, h), Conversion::BgraToRgba)
+        .and_then(|readback| readback.wait(&r.device))
+        .unwrap();
 
-    r.read(&framebuffer.target, move |data| {
-        let file = File::create("screenshot.png").unwrap();
-        let png = PNGEncoder::new(file);
+    let file = File::create("screenshot.png").unwrap();
+    let png = PNGEncoder::new(file);
 
-        png.encode(data, w, h, ColorType::RGBA(8)).unwrap();
-    });
+    png.encode(&data, w, h, ColorType::RGBA(8)).unwrap();
 }
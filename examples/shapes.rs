@@ -28,8 +28,15 @@ fn main() {
     let mut r = Renderer::new(window.raw_window_handle());
     let mut win = window.inner_size().to_physical(window.hidpi_factor());
 
-    let mut pip: kit::shape2d::Pipeline =
-        r.pipeline(win.width as u32, win.height as u32, Blending::default());
+    // Render with 4x MSAA, to smooth out the edges of circles and lines.
+    let samples = 4;
+
+    let mut pip: kit::shape2d::Pipeline = r.pipeline_msaa(
+        win.width as u32,
+        win.height as u32,
+        Blending::default(),
+        samples,
+    );
 
     ///////////////////////////////////////////////////////////////////////////
     // Render loop
@@ -40,7 +47,15 @@ fn main() {
     // Cursor position.
     let (mut mx, mut my) = (0., 0.);
 
-    let mut textures = r.swap_chain(win.width as u32, win.height as u32, PresentMode::default());
+    let mut textures = r.swap_chain_msaa(
+        win.width as u32,
+        win.height as u32,
+        PresentMode::default(),
+        samples,
+    );
+
+    // The shape buffers, which are updated every frame.
+    let mut buffer: Option<Mesh<kit::shape2d::Vertex>> = None;
     
     event_loop.run(move |event, _, control_flow| match event {
         Event::NewEvents(StartCause::Init) => {
@@ -75,8 +90,9 @@ fn main() {
 
                 let (w, h) = (win.width as u32, win.height as u32);
 
-                pip.resize(w, h);
-                textures = r.swap_chain(w, h, PresentMode::default());
+                if let Some((w, h)) = textures.resize(&r.device, w, h) {
+                    pip.resize(w, h);
+                }
             }
             WindowEvent::RedrawRequested => {
                 let rows = (win.height as f32 / sh) as u32;
@@ -131,14 +147,19 @@ fn main() {
                     }
                 }
 
-                let buffer = batch.finish(&r);
-
                 ///////////////////////////////////////////////////////////////////////////
-                // Create frame
+                // Create frame & upload shapes
                 ///////////////////////////////////////////////////////////////////////////
 
                 let mut frame = r.frame();
 
+                // Re-use the buffers of the previous frame, if any.
+                if let Some(ref mut buffer) = buffer {
+                    batch.upload(&mut r, buffer, &mut frame);
+                } else {
+                    buffer = Some(batch.finish(&r));
+                }
+
                 ///////////////////////////////////////////////////////////////////////////
                 // Draw frame
                 ///////////////////////////////////////////////////////////////////////////
@@ -148,7 +169,7 @@ fn main() {
                     let pass = &mut frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &out);
 
                     pass.set_pipeline(&pip);
-                    pass.draw_buffer(&buffer);
+                    pass.draw_mesh(buffer.as_ref().unwrap());
                 }
                 r.submit(frame);
             }
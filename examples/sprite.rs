@@ -39,7 +39,12 @@ fn main() {
     // Setup sampler & load texture
     ///////////////////////////////////////////////////////////////////////////
 
-    let sampler = r.sampler(Filter::Nearest, Filter::Nearest);
+    // Sprite sheets are clamped, so that frames at the edge of the sheet
+    // don't bleed into the opposite edge.
+    let sampler = r.sampler_with(
+        &SamplerDescriptor::new(Filter::Nearest, Filter::Nearest)
+            .address_mode(AddressMode::ClampToEdge),
+    );
 
     let (sprite, texels) = {
         let bytes = include_bytes!("data/sprite.tga");
@@ -132,8 +137,9 @@ fn main() {
 
                     let (w, h) = (win.width as u32, win.height as u32);
 
-                    pip.resize(w, h);
-                    textures = r.swap_chain(w, h, PresentMode::default());
+                    if let Some((w, h)) = textures.resize(&r.device, w, h) {
+                        pip.resize(w, h);
+                    }
                 }
                 _ => (),
             },
@@ -10,6 +10,7 @@ use rgx::kit::shape2d::{Fill, Shape, Stroke};
 
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Duration;
 
 use raw_window_handle::HasRawWindowHandle;
 use winit::{
@@ -23,7 +24,7 @@ fn main() {
 
     let event_loop = EventLoop::new();
     let window = Window::new(&event_loop).unwrap();
-    let mut size = window.inner_size().to_physical(window.hidpi_factor());
+    let size = window.inner_size().to_physical(window.hidpi_factor());
 
     // Setup renderer
     let mut renderer = Renderer::new(window.raw_window_handle());
@@ -45,9 +46,14 @@ fn main() {
                 (s.width as u32, s.height as u32)
             };
 
-            if chain.width != w || chain.height != h {
+            if let Some((w, h)) = chain.resize(&renderer.device, w, h) {
                 pipeline.resize(w, h);
-                chain = renderer.swap_chain(w, h, PresentMode::NoVsync);
+            }
+
+            // Nothing to draw to while the window is minimized.
+            if chain.is_empty() {
+                thread::sleep(Duration::from_millis(16));
+                continue;
             }
 
             let (mx, my) = {
@@ -55,7 +61,7 @@ fn main() {
             };
             
             let buffer = shape2d::Batch::singleton(Shape::Circle(
-                Point2::new(mx, size.height as f32 - my),
+                Point2::new(mx, h as f32 - my),
                 20.,
                 32,
                 Stroke::NONE,
@@ -69,7 +75,7 @@ fn main() {
                 let mut pass = frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &output);
 
                 pass.set_pipeline(&pipeline);
-                pass.draw_buffer(&buffer);
+                pass.draw_mesh(&buffer);
             }
             renderer.submit(frame);
         }
@@ -83,10 +89,8 @@ fn main() {
                 m.1 = position.y as f32;
             }
             WindowEvent::Resized(s) => {
-                size = s.to_physical(window.hidpi_factor());
-
                 let mut shared = shared_size.lock().unwrap();
-                *shared = size;
+                *shared = s.to_physical(window.hidpi_factor());
             }
             WindowEvent::KeyboardInput {
                 input:
@@ -1,15 +1,127 @@
 #![deny(clippy::all, clippy::use_self)]
 #![allow(clippy::cast_lossless)]
 
+use std::borrow::Cow;
 use std::fmt;
+use std::marker::PhantomData;
 use std::ops::Range;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
 use raw_window_handle::RawWindowHandle;
 
 use crate::math;
 use crate::math::{Point2, Vector2};
 
+mod reflect;
+
+///////////////////////////////////////////////////////////////////////////
+// Error
+///////////////////////////////////////////////////////////////////////////
+
+/// An error returned by the fallible parts of the core API.
+///
+/// There is no error for a missing graphics adapter: wgpu 0.3's
+/// `Instance::request_adapter` returns an `Adapter` rather than an `Option`,
+/// and panics itself when no adapter is available, so device creation has no
+/// failure to report.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The device was created without a surface, eg. with [`Renderer::headless`].
+    NoSurface,
+    /// The swap chain has a size of zero, eg. because its window is minimized.
+    EmptySwapChain,
+    /// The shader source could not be read as SPIR-V.
+    InvalidSpirv(String),
+    /// The number of bindings doesn't match the number of slots in the layout.
+    BindingCountMismatch { expected: usize, actual: usize },
+    /// The texel buffer length doesn't match the size of the target, in bytes.
+    TexelBufferSizeMismatch { expected: usize, actual: usize },
+    /// The transfer region is larger than the target texture.
+    TransferOutOfBounds { width: u32, height: u32 },
+    /// The blit source and destination rectangles differ in size.
+    BlitSizeMismatch(Rect<f32>, Rect<f32>),
+    /// The blit rectangle lies outside of the texture.
+    BlitOutOfBounds(Rect<f32>),
+    /// The region to read lies outside of the texture, or is empty.
+    ReadOutOfBounds(Rect<u32>),
+    /// Reading a buffer back into host memory failed.
+    ReadbackFailed(String),
+    /// A shader could not be read from disk, or failed to compile to SPIR-V.
+    ShaderCompilation(String),
+    /// A shader's vertex inputs or bindings don't match the pipeline description.
+    ShaderMismatch(String),
+    /// A vertex buffer doesn't match the vertex formats of the pipeline it is
+    /// drawn with, or a vertex type is smaller than its vertex formats.
+    VertexFormatMismatch(String),
+    /// The range to write lies outside of the buffer, in elements of the
+    /// buffer.
+    WriteOutOfBounds { range: Range<usize>, size: usize },
+    /// The indices are of a different format than the index buffer.
+    IndexFormatMismatch {
+        expected: IndexFormat,
+        actual: IndexFormat,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSurface => write!(
+                f,
+                "device has no surface to present to: it was created headless"
+            ),
+            Self::EmptySwapChain => write!(
+                f,
+                "swap chain has no texels to present: its width or height is zero"
+            ),
+            Self::InvalidSpirv(err) => write!(f, "invalid SPIR-V: {}", err),
+            Self::BindingCountMismatch { expected, actual } => write!(
+                f,
+                "layout slot count does not match bindings: expected {}, got {}",
+                expected, actual
+            ),
+            Self::TexelBufferSizeMismatch { expected, actual } => write!(
+                f,
+                "incorrect length for texel buffer: expected {} bytes, got {}",
+                expected, actual
+            ),
+            Self::TransferOutOfBounds { width, height } => write!(
+                f,
+                "transfer size {}x{} must be <= texture size",
+                width, height
+            ),
+            Self::BlitSizeMismatch(src, dst) => write!(
+                f,
+                "source and destination rectangles must be of the same size: {:?} and {:?}",
+                src, dst
+            ),
+            Self::BlitOutOfBounds(r) => write!(f, "blit rectangle {:?} is out of bounds", r),
+            Self::ReadOutOfBounds(r) => write!(f, "read region {:?} is out of bounds", r),
+            Self::ReadbackFailed(err) => write!(f, "readback failed: {}", err),
+            Self::ShaderCompilation(err) => write!(f, "shader compilation failed: {}", err),
+            Self::ShaderMismatch(err) => {
+                write!(f, "shader does not match pipeline description: {}", err)
+            }
+            Self::VertexFormatMismatch(err) => {
+                write!(f, "vertex buffer does not match pipeline: {}", err)
+            }
+            Self::WriteOutOfBounds { range, size } => write!(
+                f,
+                "write range {}..{} is out of bounds for buffer of size {}",
+                range.start, range.end, size
+            ),
+            Self::IndexFormatMismatch { expected, actual } => write!(
+                f,
+                "index format mismatch: expected {:?}, got {:?}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 ///////////////////////////////////////////////////////////////////////////
 // Rgba8
 ///////////////////////////////////////////////////////////////////////////
@@ -447,13 +559,138 @@ impl ShaderStage {
     }
 }
 
+/// Compile GLSL source to SPIR-V, for use with
+/// [`Renderer::try_pipeline_with_shaders`]. Requires the `glsl` feature.
+#[cfg(feature = "glsl")]
+pub fn compile_glsl(source: &str, stage: ShaderStage) -> Result<Vec<u8>, Error> {
+    use std::io::Read;
+
+    let ty = match stage {
+        ShaderStage::Vertex => glsl_to_spirv::ShaderType::Vertex,
+        ShaderStage::Fragment => glsl_to_spirv::ShaderType::Fragment,
+        ShaderStage::Compute => glsl_to_spirv::ShaderType::Compute,
+    };
+    let mut file = glsl_to_spirv::compile(source, ty).map_err(Error::ShaderCompilation)?;
+    let mut spv = Vec::new();
+
+    file.read_to_end(&mut spv)
+        .map_err(|err| Error::ShaderCompilation(err.to_string()))?;
+
+    Ok(spv)
+}
+
+/// A pair of GLSL vertex and fragment shader files, which can be watched for
+/// changes and recompiled, eg. with [`Renderer::reload_pipeline`].
+/// Requires the `glsl` feature.
+#[cfg(feature = "glsl")]
+#[derive(Debug, Clone)]
+pub struct ShaderFiles {
+    pub vertex: std::path::PathBuf,
+    pub fragment: std::path::PathBuf,
+
+    /// The modification time seen by the last compilation, if any. This is
+    /// `Some(None)` if either file couldn't be read then.
+    modified: Option<Option<std::time::SystemTime>>,
+}
+
+#[cfg(feature = "glsl")]
+impl ShaderFiles {
+    pub fn new<P: Into<std::path::PathBuf>>(vertex: P, fragment: P) -> Self {
+        Self {
+            vertex: vertex.into(),
+            fragment: fragment.into(),
+            modified: None,
+        }
+    }
+
+    /// Whether either file changed since it was last compiled, including
+    /// being created or removed. This is always the case before the first
+    /// compilation.
+    pub fn changed(&self) -> bool {
+        match self.modified {
+            Some(compiled) => last_modified(&[&self.vertex, &self.fragment]) != compiled,
+            None => true,
+        }
+    }
+
+    /// Read and compile both files to SPIR-V, as `(vertex, fragment)`.
+    pub fn compile(&mut self) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        // Record the modification time first, so that failed compilations
+        // are only retried once the files change again.
+        self.modified = Some(last_modified(&[&self.vertex, &self.fragment]));
+
+        let vs = compile_glsl(&read_glsl(&self.vertex)?, ShaderStage::Vertex)?;
+        let fs = compile_glsl(&read_glsl(&self.fragment)?, ShaderStage::Fragment)?;
+
+        Ok((vs, fs))
+    }
+}
+
+/// A GLSL compute shader file, which can be watched for changes and
+/// recompiled, eg. with [`Renderer::reload_compute_pipeline`].
+/// Requires the `glsl` feature.
+#[cfg(feature = "glsl")]
+#[derive(Debug, Clone)]
+pub struct ComputeShaderFile {
+    pub path: std::path::PathBuf,
+
+    /// The modification time seen by the last compilation, if any. See
+    /// [`ShaderFiles`].
+    modified: Option<Option<std::time::SystemTime>>,
+}
+
+#[cfg(feature = "glsl")]
+impl ComputeShaderFile {
+    pub fn new<P: Into<std::path::PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            modified: None,
+        }
+    }
+
+    /// Whether the file changed since it was last compiled, including being
+    /// created or removed. This is always the case before the first
+    /// compilation.
+    pub fn changed(&self) -> bool {
+        match self.modified {
+            Some(compiled) => last_modified(&[&self.path]) != compiled,
+            None => true,
+        }
+    }
+
+    /// Read and compile the file to SPIR-V.
+    pub fn compile(&mut self) -> Result<Vec<u8>, Error> {
+        self.modified = Some(last_modified(&[&self.path]));
+
+        compile_glsl(&read_glsl(&self.path)?, ShaderStage::Compute)
+    }
+}
+
+/// The latest modification time of the given files, or `None` if any of them
+/// can't be read.
+#[cfg(feature = "glsl")]
+fn last_modified(paths: &[&std::path::Path]) -> Option<std::time::SystemTime> {
+    paths
+        .iter()
+        .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+        .try_fold(std::time::UNIX_EPOCH, |latest, m| Some(latest.max(m?)))
+}
+
+#[cfg(feature = "glsl")]
+fn read_glsl(path: &std::path::Path) -> Result<String, Error> {
+    std::fs::read_to_string(path)
+        .map_err(|err| Error::ShaderCompilation(format!("{}: {}", path.display(), err)))
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 /// Canvas
 ///////////////////////////////////////////////////////////////////////////////
 
+/// A render target whose texels can be written to directly. The writes are
+/// recorded into the given [`Frame`], and happen when it is submitted.
 pub trait Canvas {
-    fn clear(&self, color: Rgba, device: &mut Device, encoder: &mut wgpu::CommandEncoder);
-    fn fill(&self, buf: &[u8], device: &mut Device, encoder: &mut wgpu::CommandEncoder);
+    fn clear(&self, color: Rgba, device: &mut Device, frame: &mut Frame) -> Result<(), Error>;
+    fn fill(&self, buf: &[u8], device: &mut Device, frame: &mut Frame) -> Result<(), Error>;
     fn transfer(
         &self,
         buf: &[u8],
@@ -462,9 +699,9 @@ pub trait Canvas {
         tw: u32,
         th: u32,
         device: &mut Device,
-        encoder: &mut wgpu::CommandEncoder,
-    );
-    fn blit(&self, from: Rect<f32>, dst: Rect<f32>, encoder: &mut wgpu::CommandEncoder);
+        frame: &mut Frame,
+    ) -> Result<(), Error>;
+    fn blit(&self, from: Rect<f32>, dst: Rect<f32>, frame: &mut Frame) -> Result<(), Error>;
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -528,6 +765,103 @@ impl Bind for UniformBuffer {
     }
 }
 
+/// The alignment of dynamic buffer offsets, and of uploads staged for
+/// buffer copies, in bytes.
+///
+/// wgpu 0.3 doesn't report the alignment a device requires. This is the value
+/// wgpu-native uses for all devices, which it checks every adapter's own
+/// requirement against.
+pub const BUFFER_ALIGNMENT: u64 = 256;
+
+/// A uniform buffer holding a number of `T` entries, each padded to the
+/// dynamic offset alignment, so that a single entry can be selected when
+/// binding it. Must be bound as [`BindingType::UniformBufferDynamic`].
+///
+/// For example, to bind the third entry of a buffer `buf` in `group`:
+///
+/// ```ignore
+/// pass.set_dynamic_binding(&group, &buf, 2);
+/// ```
+pub struct DynamicBuffer<T> {
+    wgpu: wgpu::Buffer,
+    capacity: usize,
+    marker: PhantomData<T>,
+}
+
+impl<T> DynamicBuffer<T> {
+    /// The alignment of dynamic offsets. See [`BUFFER_ALIGNMENT`].
+    pub const ALIGNMENT: usize = BUFFER_ALIGNMENT as usize;
+
+    /// The size of an entry, including padding, in bytes.
+    pub fn stride() -> usize {
+        let size = std::mem::size_of::<T>().max(1);
+        size.div_ceil(Self::ALIGNMENT) * Self::ALIGNMENT
+    }
+
+    /// The number of entries the buffer holds.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The dynamic offset of the entry at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn offset(&self, index: usize) -> u64 {
+        assert!(
+            index < self.capacity,
+            "dynamic buffer index {} is out of bounds for capacity {}",
+            index,
+            self.capacity
+        );
+        (index * Self::stride()) as u64
+    }
+}
+
+impl<T> Bind for DynamicBuffer<T> {
+    fn binding(&self, index: u32) -> wgpu::Binding<'_> {
+        wgpu::Binding {
+            binding: index,
+            resource: wgpu::BindingResource::Buffer {
+                buffer: &self.wgpu,
+                range: 0..(std::mem::size_of::<T>() as wgpu::BufferAddress),
+            },
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Storage
+///////////////////////////////////////////////////////////////////////////////
+
+/// A buffer shaders can read from and write to, eg. for large arrays of
+/// per-sprite data. Bound as [`BindingType::StorageBuffer`] or
+/// [`BindingType::ReadonlyStorageBuffer`].
+pub struct StorageBuffer {
+    wgpu: wgpu::Buffer,
+    size: usize,
+}
+
+impl StorageBuffer {
+    /// The size of the buffer, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl Bind for StorageBuffer {
+    fn binding(&self, index: u32) -> wgpu::Binding<'_> {
+        wgpu::Binding {
+            binding: index,
+            resource: wgpu::BindingResource::Buffer {
+                buffer: &self.wgpu,
+                range: 0..(self.size as wgpu::BufferAddress),
+            },
+        }
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 /// Framebuffer
 ///////////////////////////////////////////////////////////////////////////////
@@ -535,6 +869,10 @@ impl Bind for UniformBuffer {
 #[allow(dead_code)]
 pub struct Framebuffer {
     pub texture: Texture,
+
+    msaa: Option<Multisample>,
+    /// Usage in addition to the default framebuffer usage.
+    usage: wgpu::TextureUsage,
 }
 
 impl Framebuffer {
@@ -542,6 +880,34 @@ impl Framebuffer {
         (self.texture.w * self.texture.h) as usize
     }
 
+    /// Recreate the framebuffer with a new size, keeping its format, sample
+    /// count and usage. The contents are discarded, and binding groups the
+    /// framebuffer was bound to have to be recreated.
+    pub fn resize(&mut self, device: &Device, w: u32, h: u32) {
+        if (self.texture.w, self.texture.h) == (w, h) {
+            return;
+        }
+        let format = self.texture.format;
+
+        *self = Self {
+            msaa: device.create_multisample(w, h, format, self.samples()),
+            ..device.create_framebuffer_with(w, h, format, self.usage)
+        };
+    }
+
+    /// The number of samples per texel rendered to. Multisampled framebuffers
+    /// are resolved into [`Framebuffer::texture`] at the end of every pass.
+    pub fn samples(&self) -> u32 {
+        self.msaa.as_ref().map_or(1, |ms| ms.samples)
+    }
+
+    /// The multisampled texture passes render to, if any. It can be bound as
+    /// a [`BindingType::SampledTextureMultisampled`], eg. to resolve it in a
+    /// custom way.
+    pub fn multisample(&self) -> Option<&Multisample> {
+        self.msaa.as_ref()
+    }
+
     pub fn width(&self) -> u32 {
         self.texture.w
     }
@@ -561,17 +927,12 @@ impl Bind for Framebuffer {
 }
 
 impl Canvas for Framebuffer {
-    fn clear(&self, color: Rgba, device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
-        Texture::clear(
-            &self.texture,
-            Bgra8::from(Rgba8::from(color)),
-            device,
-            encoder,
-        );
+    fn clear(&self, color: Rgba, device: &mut Device, frame: &mut Frame) -> Result<(), Error> {
+        Texture::clear(&self.texture, color, device, frame)
     }
 
-    fn fill(&self, buf: &[u8], device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
-        Texture::fill(&self.texture, buf, device, encoder);
+    fn fill(&self, buf: &[u8], device: &mut Device, frame: &mut Frame) -> Result<(), Error> {
+        Texture::fill(&self.texture, buf, device, frame)
     }
 
     fn transfer(
@@ -582,13 +943,13 @@ impl Canvas for Framebuffer {
         tw: u32,
         th: u32,
         device: &mut Device,
-        encoder: &mut wgpu::CommandEncoder,
-    ) {
-        Texture::transfer(&self.texture, buf, w, h, tw, th, device, encoder);
+        frame: &mut Frame,
+    ) -> Result<(), Error> {
+        Texture::transfer(&self.texture, buf, w, h, tw, th, device, frame)
     }
 
-    fn blit(&self, from: Rect<f32>, dst: Rect<f32>, encoder: &mut wgpu::CommandEncoder) {
-        Texture::blit(&self.texture, from, dst, encoder);
+    fn blit(&self, from: Rect<f32>, dst: Rect<f32>, frame: &mut Frame) -> Result<(), Error> {
+        Texture::blit(&self.texture, from, dst, frame)
     }
 }
 
@@ -596,6 +957,237 @@ impl TextureView for Framebuffer {
     fn texture_view(&self) -> &wgpu::TextureView {
         &self.texture.view
     }
+
+    fn multisample_view(&self) -> Option<&wgpu::TextureView> {
+        self.msaa.as_ref().map(|ms| &ms.view)
+    }
+}
+
+/// A multisampled render target, which is resolved into a single-sampled
+/// texture at the end of each pass.
+pub struct Multisample {
+    // Only kept around so that the view stays valid.
+    #[allow(dead_code)]
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    samples: u32,
+}
+
+impl Multisample {
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+}
+
+impl Bind for Multisample {
+    fn binding(&self, index: u32) -> wgpu::Binding<'_> {
+        wgpu::Binding {
+            binding: index,
+            resource: wgpu::BindingResource::TextureView(&self.view),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Depth & Stencil
+///////////////////////////////////////////////////////////////////////////////
+
+/// A depth/stencil buffer, which can be attached to a pass with
+/// [`Frame::pass_with_depth`].
+pub struct DepthBuffer {
+    // Only kept around so that the view stays valid.
+    #[allow(dead_code)]
+    wgpu: wgpu::Texture,
+    view: wgpu::TextureView,
+    format: DepthFormat,
+    samples: u32,
+
+    pub w: u32,
+    pub h: u32,
+}
+
+impl DepthBuffer {
+    pub fn format(&self) -> DepthFormat {
+        self.format
+    }
+
+    /// The number of samples per texel. This must match the sample count of
+    /// the color targets it's used with.
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+}
+
+/// The format of a depth/stencil buffer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum DepthFormat {
+    Depth32Float,
+    Depth24Plus,
+    #[default]
+    Depth24PlusStencil8,
+}
+
+impl DepthFormat {
+    pub fn has_stencil(self) -> bool {
+        self == Self::Depth24PlusStencil8
+    }
+
+    fn to_wgpu(self) -> wgpu::TextureFormat {
+        match self {
+            Self::Depth32Float => wgpu::TextureFormat::Depth32Float,
+            Self::Depth24Plus => wgpu::TextureFormat::Depth24Plus,
+            Self::Depth24PlusStencil8 => wgpu::TextureFormat::Depth24PlusStencil8,
+        }
+    }
+}
+
+/// A comparison function, used for depth and stencil tests.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CompareFunction {
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+impl CompareFunction {
+    fn to_wgpu(self) -> wgpu::CompareFunction {
+        match self {
+            Self::Never => wgpu::CompareFunction::Never,
+            Self::Less => wgpu::CompareFunction::Less,
+            Self::Equal => wgpu::CompareFunction::Equal,
+            Self::LessEqual => wgpu::CompareFunction::LessEqual,
+            Self::Greater => wgpu::CompareFunction::Greater,
+            Self::NotEqual => wgpu::CompareFunction::NotEqual,
+            Self::GreaterEqual => wgpu::CompareFunction::GreaterEqual,
+            Self::Always => wgpu::CompareFunction::Always,
+        }
+    }
+}
+
+/// What happens to a stencil value when a stencil test passes or fails.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StencilOp {
+    Keep,
+    Zero,
+    /// Replace the value with the pass's stencil reference.
+    /// See [`Pass::set_stencil_reference`].
+    Replace,
+    Invert,
+    IncrementClamp,
+    DecrementClamp,
+    IncrementWrap,
+    DecrementWrap,
+}
+
+impl StencilOp {
+    fn to_wgpu(self) -> wgpu::StencilOperation {
+        match self {
+            Self::Keep => wgpu::StencilOperation::Keep,
+            Self::Zero => wgpu::StencilOperation::Zero,
+            Self::Replace => wgpu::StencilOperation::Replace,
+            Self::Invert => wgpu::StencilOperation::Invert,
+            Self::IncrementClamp => wgpu::StencilOperation::IncrementClamp,
+            Self::DecrementClamp => wgpu::StencilOperation::DecrementClamp,
+            Self::IncrementWrap => wgpu::StencilOperation::IncrementWrap,
+            Self::DecrementWrap => wgpu::StencilOperation::DecrementWrap,
+        }
+    }
+}
+
+/// The stencil test and operations for one side of a primitive.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StencilFace {
+    pub compare: CompareFunction,
+    pub fail_op: StencilOp,
+    pub depth_fail_op: StencilOp,
+    pub pass_op: StencilOp,
+}
+
+impl StencilFace {
+    /// A stencil face that always passes and leaves the stencil untouched.
+    pub const IGNORE: Self = Self {
+        compare: CompareFunction::Always,
+        fail_op: StencilOp::Keep,
+        depth_fail_op: StencilOp::Keep,
+        pass_op: StencilOp::Keep,
+    };
+
+    fn to_wgpu(self) -> wgpu::StencilStateFaceDescriptor {
+        wgpu::StencilStateFaceDescriptor {
+            compare: self.compare.to_wgpu(),
+            fail_op: self.fail_op.to_wgpu(),
+            depth_fail_op: self.depth_fail_op.to_wgpu(),
+            pass_op: self.pass_op.to_wgpu(),
+        }
+    }
+}
+
+/// The depth and stencil state of a pipeline.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DepthStencilState {
+    pub format: DepthFormat,
+    pub depth_write: bool,
+    pub depth_compare: CompareFunction,
+    pub stencil_front: StencilFace,
+    pub stencil_back: StencilFace,
+    pub stencil_read_mask: u32,
+    pub stencil_write_mask: u32,
+}
+
+impl DepthStencilState {
+    /// A depth test keeping the fragments closest to the viewer, with the
+    /// stencil test disabled.
+    pub fn new(format: DepthFormat) -> Self {
+        Self {
+            format,
+            depth_write: true,
+            depth_compare: CompareFunction::Less,
+            stencil_front: StencilFace::IGNORE,
+            stencil_back: StencilFace::IGNORE,
+            stencil_read_mask: !0,
+            stencil_write_mask: !0,
+        }
+    }
+
+    pub fn depth_write(mut self, enabled: bool) -> Self {
+        self.depth_write = enabled;
+        self
+    }
+
+    pub fn depth_compare(mut self, compare: CompareFunction) -> Self {
+        self.depth_compare = compare;
+        self
+    }
+
+    /// Set the stencil test and operations of both sides.
+    pub fn stencil(mut self, face: StencilFace) -> Self {
+        self.stencil_front = face;
+        self.stencil_back = face;
+        self
+    }
+
+    pub fn stencil_masks(mut self, read: u32, write: u32) -> Self {
+        self.stencil_read_mask = read;
+        self.stencil_write_mask = write;
+        self
+    }
+
+    fn to_wgpu(self) -> wgpu::DepthStencilStateDescriptor {
+        wgpu::DepthStencilStateDescriptor {
+            format: self.format.to_wgpu(),
+            depth_write_enabled: self.depth_write,
+            depth_compare: self.depth_compare.to_wgpu(),
+            stencil_front: self.stencil_front.to_wgpu(),
+            stencil_back: self.stencil_back.to_wgpu(),
+            stencil_read_mask: self.stencil_read_mask,
+            stencil_write_mask: self.stencil_write_mask,
+        }
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -607,6 +1199,9 @@ pub struct Texture {
     wgpu: wgpu::Texture,
     view: wgpu::TextureView,
     extent: wgpu::Extent3d,
+    format: TextureFormat,
+    mip_levels: u32,
+    layers: u32,
 
     pub w: u32,
     pub h: u32,
@@ -622,49 +1217,92 @@ impl Texture {
         }
     }
 
-    fn clear<T>(
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// The number of mip levels allocated for this texture.
+    pub fn mip_levels(&self) -> u32 {
+        self.mip_levels
+    }
+
+    /// The number of array layers of this texture. Only textures created with
+    /// [`Device::create_texture_array`] have more than one.
+    pub fn layers(&self) -> u32 {
+        self.layers
+    }
+
+    /// The number of mip levels in a full chain for a texture of the given size.
+    pub fn full_mip_levels(w: u32, h: u32) -> u32 {
+        32 - w.max(h).max(1).leading_zeros()
+    }
+
+    /// Create a view of a single mip level of an array layer.
+    fn level_view(&self, level: u32, layer: u32) -> wgpu::TextureView {
+        self.wgpu.create_view(&wgpu::TextureViewDescriptor {
+            format: self.format.to_wgpu(),
+            dimension: wgpu::TextureViewDimension::D2,
+            aspect: wgpu::TextureAspect::All,
+            base_mip_level: level,
+            level_count: 1,
+            base_array_layer: layer,
+            array_layer_count: 1,
+        })
+    }
+
+    /// Check whether the given rectangle fits inside the texture.
+    fn contains(&self, r: Rect<f32>) -> bool {
+        r.x1.min(r.x2) >= 0.
+            && r.y1.min(r.y2) >= 0.
+            && r.x1.max(r.x2) <= self.w as f32
+            && r.y1.max(r.y2) <= self.h as f32
+    }
+
+    fn clear(
         texture: &Texture,
-        color: T,
+        color: Rgba,
         device: &mut Device,
-        encoder: &mut wgpu::CommandEncoder,
-    ) where
-        T: Into<Rgba8> + Clone,
-    {
-        let mut texels: Vec<T> = Vec::with_capacity(texture.w as usize * texture.h as usize);
-        texels.resize(texture.w as usize * texture.h as usize, color);
-
-        let (head, body, tail) = unsafe { texels.align_to::<u8>() };
-        assert!(head.is_empty());
-        assert!(tail.is_empty());
+        frame: &mut Frame,
+    ) -> Result<(), Error> {
+        let texel = texture.format.encode(color);
+        let texels =
+            texel.repeat(texture.w as usize * texture.h as usize * texture.layers as usize);
 
-        Self::fill(texture, body, device, encoder);
+        Self::fill(texture, texels.as_slice(), device, frame)
     }
 
     fn fill(
         texture: &Texture,
         texels: &[u8],
         device: &mut Device,
-        encoder: &mut wgpu::CommandEncoder,
-    ) {
-        assert_eq!(
-            texels.len() as u32,
-            texture.w * texture.h * 4,
-            "fatal: incorrect length for texel buffer"
-        );
+        frame: &mut Frame,
+    ) -> Result<(), Error> {
+        // Array textures are filled one layer after the other.
+        let layer = texture.w as usize * texture.h as usize * texture.format.bytesize();
+        let expected = layer * texture.layers as usize;
+
+        if texels.len() != expected {
+            return Err(Error::TexelBufferSizeMismatch {
+                expected,
+                actual: texels.len(),
+            });
+        }
 
-        let buf = device
-            .device
-            .create_buffer_mapped(texels.len(), wgpu::BufferUsage::COPY_SRC)
-            .fill_from_slice(&texels);
+        let (buf, offset) = device.stage(texels, 1, &mut frame.staged);
 
-        Self::copy(
-            &texture.wgpu,
-            texture.w,
-            texture.h,
-            texture.extent,
-            &buf,
-            encoder,
-        );
+        for i in 0..texture.layers {
+            Self::copy(
+                texture,
+                texture.w,
+                texture.h,
+                texture.extent,
+                i,
+                (buf, offset + (i as usize * layer) as wgpu::BufferAddress),
+                &mut frame.encoder,
+            );
+        }
+
+        Ok(())
     }
 
     fn transfer(
@@ -675,44 +1313,54 @@ impl Texture {
         transfer_w: u32,
         transfer_h: u32,
         device: &mut Device,
-        encoder: &mut wgpu::CommandEncoder,
-    ) {
-        assert_eq!(
-            (texels.len() as u32 / 4),
-            width * height,
-            "fatal: incorrect length for texel buffer"
-        );
-        assert!(
-            transfer_w * transfer_h <= texture.w * texture.h,
-            "fatal: transfer size must be <= texture size"
-        );
+        frame: &mut Frame,
+    ) -> Result<(), Error> {
+        let expected = width as usize * height as usize * texture.format.bytesize();
+
+        if texels.len() != expected {
+            return Err(Error::TexelBufferSizeMismatch {
+                expected,
+                actual: texels.len(),
+            });
+        }
+        if transfer_w > texture.w || transfer_h > texture.h {
+            return Err(Error::TransferOutOfBounds {
+                width: transfer_w,
+                height: transfer_h,
+            });
+        }
 
-        let buf = device
-            .device
-            .create_buffer_mapped(texels.len(), wgpu::BufferUsage::COPY_SRC)
-            .fill_from_slice(&texels);
+        let (buf, offset) = device.stage(texels, 1, &mut frame.staged);
 
         let extent = wgpu::Extent3d {
             width: transfer_w,
             height: transfer_h,
             depth: 1,
         };
-        Self::copy(&texture.wgpu, width, height, extent, &buf, encoder);
+        Self::copy(
+            texture,
+            width,
+            height,
+            extent,
+            0,
+            (buf, offset),
+            &mut frame.encoder,
+        );
+
+        Ok(())
     }
 
-    fn blit(&self, src: Rect<f32>, dst: Rect<f32>, encoder: &mut wgpu::CommandEncoder) {
-        assert_eq!(
-            src.width(),
-            dst.width(),
-            "source and destination rectangles must be of the same size"
-        );
-        assert_eq!(
-            src.height(),
-            dst.height(),
-            "source and destination rectangles must be of the same size"
-        );
+    fn blit(&self, src: Rect<f32>, dst: Rect<f32>, frame: &mut Frame) -> Result<(), Error> {
+        if src.width() != dst.width() || src.height() != dst.height() {
+            return Err(Error::BlitSizeMismatch(src, dst));
+        }
+        for r in &[src, dst] {
+            if !self.contains(*r) {
+                return Err(Error::BlitOutOfBounds(*r));
+            }
+        }
 
-        encoder.copy_texture_to_texture(
+        frame.encoder.copy_texture_to_texture(
             wgpu::TextureCopyView {
                 texture: &self.wgpu,
                 mip_level: 0,
@@ -739,27 +1387,29 @@ impl Texture {
                 depth: 1,
             },
         );
+        Ok(())
     }
 
     fn copy(
-        texture: &wgpu::Texture,
+        texture: &Self,
         w: u32,
         h: u32,
         extent: wgpu::Extent3d,
-        buffer: &wgpu::Buffer,
+        layer: u32,
+        (buffer, offset): (&wgpu::Buffer, wgpu::BufferAddress),
         encoder: &mut wgpu::CommandEncoder,
     ) {
         encoder.copy_buffer_to_texture(
             wgpu::BufferCopyView {
                 buffer,
-                offset: 0,
-                row_pitch: 4 * w,
+                offset,
+                row_pitch: texture.format.bytesize() as u32 * w,
                 image_height: h,
             },
             wgpu::TextureCopyView {
-                texture,
+                texture: &texture.wgpu,
                 mip_level: 0,
-                array_layer: 0,
+                array_layer: layer,
                 origin: wgpu::Origin3d {
                     x: 0.0,
                     y: 0.0,
@@ -781,12 +1431,12 @@ impl Bind for Texture {
 }
 
 impl Canvas for Texture {
-    fn fill(&self, buf: &[u8], device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
-        Texture::fill(&self, buf, device, encoder);
+    fn fill(&self, buf: &[u8], device: &mut Device, frame: &mut Frame) -> Result<(), Error> {
+        Self::fill(self, buf, device, frame)
     }
 
-    fn clear(&self, color: Rgba, device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
-        Texture::clear(&self, color, device, encoder);
+    fn clear(&self, color: Rgba, device: &mut Device, frame: &mut Frame) -> Result<(), Error> {
+        Self::clear(self, color, device, frame)
     }
 
     fn transfer(
@@ -797,13 +1447,13 @@ impl Canvas for Texture {
         tw: u32,
         th: u32,
         device: &mut Device,
-        encoder: &mut wgpu::CommandEncoder,
-    ) {
-        Texture::transfer(&self, buf, w, h, tw, th, device, encoder);
+        frame: &mut Frame,
+    ) -> Result<(), Error> {
+        Self::transfer(self, buf, w, h, tw, th, device, frame)
     }
 
-    fn blit(&self, src: Rect<f32>, dst: Rect<f32>, encoder: &mut wgpu::CommandEncoder) {
-        Texture::blit(&self, src, dst, encoder);
+    fn blit(&self, src: Rect<f32>, dst: Rect<f32>, frame: &mut Frame) -> Result<(), Error> {
+        Self::blit(self, src, dst, frame)
     }
 }
 
@@ -813,27 +1463,107 @@ impl From<Framebuffer> for Texture {
     }
 }
 
-pub struct Sampler {
-    wgpu: wgpu::Sampler,
+/// The texel format of a texture or render target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFormat {
+    /// Single-channel, eg. for masks and glyphs.
+    R8Unorm,
+    Rgba8Unorm,
+    Rgba8UnormSrgb,
+    Bgra8Unorm,
+    Bgra8UnormSrgb,
+    /// Half-float, eg. for HDR intermediate targets.
+    Rgba16Float,
 }
 
-impl Bind for Sampler {
-    fn binding(&self, index: u32) -> wgpu::Binding {
-        wgpu::Binding {
-            binding: index as u32,
-            resource: wgpu::BindingResource::Sampler(&self.wgpu),
+impl TextureFormat {
+    /// The size of a texel, in bytes.
+    pub fn bytesize(self) -> usize {
+        match self {
+            Self::R8Unorm => 1,
+            Self::Rgba8Unorm => 4,
+            Self::Rgba8UnormSrgb => 4,
+            Self::Bgra8Unorm => 4,
+            Self::Bgra8UnormSrgb => 4,
+            Self::Rgba16Float => 8,
+        }
+    }
+
+    pub fn is_bgra(self) -> bool {
+        matches!(self, Self::Bgra8Unorm | Self::Bgra8UnormSrgb)
+    }
+
+    /// Encode a color as a single texel of this format.
+    fn encode(self, color: Rgba) -> Vec<u8> {
+        let Rgba8 { r, g, b, a } = color.into();
+
+        match self {
+            Self::R8Unorm => vec![r],
+            Self::Rgba8Unorm | Self::Rgba8UnormSrgb => vec![r, g, b, a],
+            Self::Bgra8Unorm | Self::Bgra8UnormSrgb => vec![b, g, r, a],
+            Self::Rgba16Float => [color.r, color.g, color.b, color.a]
+                .iter()
+                .flat_map(|c| f16_bits(*c).to_le_bytes().to_vec())
+                .collect(),
+        }
+    }
+
+    fn to_wgpu(self) -> wgpu::TextureFormat {
+        match self {
+            Self::R8Unorm => wgpu::TextureFormat::R8Unorm,
+            Self::Rgba8Unorm => wgpu::TextureFormat::Rgba8Unorm,
+            Self::Rgba8UnormSrgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+            Self::Bgra8Unorm => wgpu::TextureFormat::Bgra8Unorm,
+            Self::Bgra8UnormSrgb => wgpu::TextureFormat::Bgra8UnormSrgb,
+            Self::Rgba16Float => wgpu::TextureFormat::Rgba16Float,
         }
     }
 }
 
-#[derive(Debug)]
+/// Convert an `f32` to the bits of an IEEE 754 half-precision float,
+/// truncating the mantissa and flushing subnormals to zero.
+fn f16_bits(x: f32) -> u16 {
+    let bits = x.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let man = bits & 0x007f_ffff;
+
+    if exp == 0xff {
+        // Infinity or NaN.
+        return sign | 0x7c00 | if man != 0 { 0x0200 } else { 0 };
+    }
+    let exp = exp - 127 + 15;
+
+    if exp >= 0x1f {
+        sign | 0x7c00
+    } else if exp <= 0 {
+        sign
+    } else {
+        sign | ((exp as u16) << 10) | (man >> 13) as u16
+    }
+}
+
+pub struct Sampler {
+    wgpu: wgpu::Sampler,
+}
+
+impl Bind for Sampler {
+    fn binding(&self, index: u32) -> wgpu::Binding {
+        wgpu::Binding {
+            binding: index as u32,
+            resource: wgpu::BindingResource::Sampler(&self.wgpu),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Filter {
     Nearest,
     Linear,
 }
 
 impl Filter {
-    fn to_wgpu(&self) -> wgpu::FilterMode {
+    fn to_wgpu(self) -> wgpu::FilterMode {
         match self {
             Filter::Nearest => wgpu::FilterMode::Nearest,
             Filter::Linear => wgpu::FilterMode::Linear,
@@ -841,33 +1571,225 @@ impl Filter {
     }
 }
 
+/// How texture coordinates outside of the `[0, 1]` range are handled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AddressMode {
+    /// Coordinates are clamped to the edge texels. Useful for texture atlases.
+    ClampToEdge,
+    /// The texture is tiled.
+    Repeat,
+    /// The texture is tiled, with every other tile mirrored.
+    MirrorRepeat,
+}
+
+impl AddressMode {
+    fn to_wgpu(self) -> wgpu::AddressMode {
+        match self {
+            Self::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+            Self::Repeat => wgpu::AddressMode::Repeat,
+            Self::MirrorRepeat => wgpu::AddressMode::MirrorRepeat,
+        }
+    }
+}
+
+/// Describes how a [`Sampler`] addresses and filters texels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SamplerDescriptor {
+    pub min_filter: Filter,
+    pub mag_filter: Filter,
+    /// The filter used between mip levels.
+    pub mipmap_filter: Filter,
+    /// The range of mip levels that can be sampled from.
+    pub lod_clamp: Range<f32>,
+    pub address_mode_u: AddressMode,
+    pub address_mode_v: AddressMode,
+    pub address_mode_w: AddressMode,
+}
+
+impl SamplerDescriptor {
+    pub fn new(min_filter: Filter, mag_filter: Filter) -> Self {
+        Self {
+            min_filter,
+            mag_filter,
+            ..Self::default()
+        }
+    }
+
+    pub fn mipmap_filter(mut self, filter: Filter) -> Self {
+        self.mipmap_filter = filter;
+        self
+    }
+
+    pub fn lod_clamp(mut self, range: Range<f32>) -> Self {
+        self.lod_clamp = range;
+        self
+    }
+
+    /// Set the address mode of all axes.
+    pub fn address_mode(self, mode: AddressMode) -> Self {
+        self.address_mode_u(mode)
+            .address_mode_v(mode)
+            .address_mode_w(mode)
+    }
+
+    pub fn address_mode_u(mut self, mode: AddressMode) -> Self {
+        self.address_mode_u = mode;
+        self
+    }
+
+    pub fn address_mode_v(mut self, mode: AddressMode) -> Self {
+        self.address_mode_v = mode;
+        self
+    }
+
+    pub fn address_mode_w(mut self, mode: AddressMode) -> Self {
+        self.address_mode_w = mode;
+        self
+    }
+
+    fn to_wgpu(&self) -> wgpu::SamplerDescriptor {
+        wgpu::SamplerDescriptor {
+            address_mode_u: self.address_mode_u.to_wgpu(),
+            address_mode_v: self.address_mode_v.to_wgpu(),
+            address_mode_w: self.address_mode_w.to_wgpu(),
+            mag_filter: self.mag_filter.to_wgpu(),
+            min_filter: self.min_filter.to_wgpu(),
+            mipmap_filter: self.mipmap_filter.to_wgpu(),
+            lod_min_clamp: self.lod_clamp.start,
+            lod_max_clamp: self.lod_clamp.end,
+            compare_function: wgpu::CompareFunction::Always,
+        }
+    }
+}
+
+impl Default for SamplerDescriptor {
+    fn default() -> Self {
+        Self {
+            min_filter: Filter::Nearest,
+            mag_filter: Filter::Nearest,
+            mipmap_filter: Filter::Nearest,
+            lod_clamp: -100.0..100.0,
+            address_mode_u: AddressMode::Repeat,
+            address_mode_v: AddressMode::Repeat,
+            address_mode_w: AddressMode::Repeat,
+        }
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 /// Vertex/Index Buffers
 ///////////////////////////////////////////////////////////////////////////////
 
-pub struct VertexBuffer {
-    pub size: u32,
+/// A type which can be stored in a [`VertexBuffer`], described by the formats
+/// of its fields, in order. Vertex types should be `#[repr(C)]`.
+///
+/// Buffers are checked against the vertex formats of the pipeline they are
+/// drawn with, see [`Pass::try_draw_instanced`].
+///
+/// There is no derive for this trait, since that would require a separate
+/// proc-macro crate. Implementations list the formats by hand, and are
+/// checked against the size of the type when a buffer is created. Padding
+/// after the last field is allowed: the size of the type is the stride of
+/// its buffers, see [`PipelineDescription::vertex_stride`].
+pub trait Vertex: 'static + Copy {
+    const FORMATS: &'static [VertexFormat];
+}
+
+pub struct VertexBuffer<V> {
+    size: u32,
+    capacity: wgpu::BufferAddress,
     wgpu: wgpu::Buffer,
+    vertex: PhantomData<V>,
+}
+
+impl<V> VertexBuffer<V> {
+    /// The number of vertices in the buffer.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// The size of the buffer, in bytes. Vertex buffers grow when they are
+    /// updated with more vertices than fit.
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
 }
 
-impl Draw for VertexBuffer {
+impl<V: Vertex> Draw for VertexBuffer<V> {
     fn draw(&self, binding: &BindingGroup, pass: &mut Pass) {
-        // TODO: If we attempt to draw more vertices than exist in the buffer, because
-        // 'size' was guessed wrong, we get a wgpu error. We should somehow try to
-        // get the pipeline layout to know here if the buffer we're trying to draw
-        // is the right size. Another option is to create buffers from the pipeline,
-        // so that we can check at creation time whether the data passed in matches
-        // the format.
         pass.set_binding(binding, &[]);
-        pass.draw_buffer(&self);
+        pass.draw_buffer(self);
     }
 }
 
 pub struct IndexBuffer {
+    size: u32,
+    capacity: wgpu::BufferAddress,
+    format: IndexFormat,
     wgpu: wgpu::Buffer,
 }
 
-#[derive(Clone, Copy)]
+impl IndexBuffer {
+    /// The number of indices in the buffer.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn format(&self) -> IndexFormat {
+        self.format
+    }
+
+    /// The size of the buffer, in bytes. Index buffers grow when they are
+    /// updated with more indices than fit.
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+}
+
+/// A type which can be stored in an [`IndexBuffer`].
+pub trait IndexType: 'static + Copy {
+    const FORMAT: IndexFormat;
+}
+
+impl IndexType for u16 {
+    const FORMAT: IndexFormat = IndexFormat::Uint16;
+}
+
+impl IndexType for u32 {
+    const FORMAT: IndexFormat = IndexFormat::Uint32;
+}
+
+/// The type of the indices in an [`IndexBuffer`]. Pipelines are created for
+/// one index format, see [`PipelineDescription::index_format`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum IndexFormat {
+    Uint16,
+    Uint32,
+}
+
+impl IndexFormat {
+    fn to_wgpu(self) -> wgpu::IndexFormat {
+        match self {
+            Self::Uint16 => wgpu::IndexFormat::Uint16,
+            Self::Uint32 => wgpu::IndexFormat::Uint32,
+        }
+    }
+}
+
+/// Indexed geometry, made of a vertex buffer and an index buffer into it.
+pub struct Mesh<V> {
+    pub vertices: VertexBuffer<V>,
+    pub indices: IndexBuffer,
+}
+
+impl<V: Vertex> Draw for Mesh<V> {
+    fn draw(&self, binding: &BindingGroup, pass: &mut Pass) {
+        pass.set_binding(binding, &[]);
+        pass.draw_mesh(self);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum VertexFormat {
     Float,
     Float2,
@@ -899,46 +1821,244 @@ impl VertexFormat {
     }
 }
 
+/// Whether a vertex buffer is advanced once per vertex, or once per instance.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum StepMode {
+    #[default]
+    Vertex,
+    Instance,
+}
+
+impl StepMode {
+    fn to_wgpu(self) -> wgpu::InputStepMode {
+        match self {
+            Self::Vertex => wgpu::InputStepMode::Vertex,
+            Self::Instance => wgpu::InputStepMode::Instance,
+        }
+    }
+}
+
 /// Describes a 'VertexBuffer' layout.
 #[derive(Default)]
 pub struct VertexLayout {
     wgpu_attrs: Vec<wgpu::VertexAttributeDescriptor>,
+    formats: Vec<VertexFormat>,
     size: usize,
+    step_mode: StepMode,
 }
 
 impl VertexLayout {
     pub fn from(formats: &[VertexFormat]) -> Self {
-        let mut vl = Self::default();
+        Self::with_step_mode(formats, StepMode::Vertex, 0)
+    }
+
+    /// Describe a vertex buffer layout with the given step mode, whose
+    /// attributes start at shader location `location`.
+    pub fn with_step_mode(formats: &[VertexFormat], step_mode: StepMode, location: u32) -> Self {
+        let mut vl = Self {
+            step_mode,
+            ..Self::default()
+        };
         for vf in formats {
             vl.wgpu_attrs.push(wgpu::VertexAttributeDescriptor {
-                shader_location: vl.wgpu_attrs.len() as u32,
+                shader_location: location + vl.wgpu_attrs.len() as u32,
                 offset: vl.size as wgpu::BufferAddress,
                 format: vf.to_wgpu(),
             });
+            vl.formats.push(*vf);
             vl.size += vf.bytesize();
         }
         vl
     }
 
+    pub fn step_mode(&self) -> StepMode {
+        self.step_mode
+    }
+
+    /// Set the stride of the layout, if given, to account for padding after
+    /// the last attribute. Fails if the stride is smaller than the attributes.
+    fn with_stride(mut self, stride: Option<usize>) -> Result<Self, Error> {
+        if let Some(stride) = stride {
+            if stride < self.size {
+                return Err(Error::VertexFormatMismatch(format!(
+                    "stride of {} bytes is smaller than the {} bytes of vertex formats {:?}",
+                    stride, self.size, self.formats
+                )));
+            }
+            self.size = stride;
+        }
+        Ok(self)
+    }
+
+    pub fn formats(&self) -> &[VertexFormat] {
+        self.formats.as_slice()
+    }
+
     fn to_wgpu(&self) -> wgpu::VertexBufferDescriptor {
         wgpu::VertexBufferDescriptor {
             stride: self.size as wgpu::BufferAddress,
-            step_mode: wgpu::InputStepMode::Vertex,
+            step_mode: self.step_mode.to_wgpu(),
             attributes: self.wgpu_attrs.as_slice(),
         }
     }
 }
 
+/// The vertex formats and stride of a vertex buffer slot, as expected by a
+/// pipeline or provided by a bound buffer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct VertexInput {
+    formats: Cow<'static, [VertexFormat]>,
+    stride: usize,
+}
+
+impl VertexInput {
+    fn of<V: Vertex>() -> Self {
+        Self {
+            formats: Cow::Borrowed(V::FORMATS),
+            stride: std::mem::size_of::<V>(),
+        }
+    }
+}
+
+/// A vertex buffer slot of a pipeline, after the first one.
+pub struct VertexSlot<'a> {
+    pub step_mode: StepMode,
+    pub formats: &'a [VertexFormat],
+    /// The size of a vertex, including padding, if it isn't the size of
+    /// `formats`. Usually the size of the slot's [`Vertex`] type.
+    pub stride: Option<usize>,
+}
+
+/// A vertex of a quad covering the target, with texture coordinates.
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct QuadVertex {
+    position: Vector2<f32>,
+    uv: Vector2<f32>,
+}
+
+impl QuadVertex {
+    fn new(x: f32, y: f32, u: f32, v: f32) -> Self {
+        Self {
+            position: Vector2::new(x, y),
+            uv: Vector2::new(u, v),
+        }
+    }
+}
+
+impl Vertex for QuadVertex {
+    const FORMATS: &'static [VertexFormat] = &[VertexFormat::Float2, VertexFormat::Float2];
+}
+
+/// The shaders, sampler and quad used by [`Renderer::generate_mipmaps`],
+/// with a pipeline for each texture format mipmaps were generated for.
+struct Mipmapper {
+    vs: Shader,
+    fs: Shader,
+    sampler: Sampler,
+    quad: VertexBuffer<QuadVertex>,
+    pipelines: Vec<(TextureFormat, Pipeline)>,
+}
+
+impl Mipmapper {
+    fn new(device: &Device) -> Self {
+        #[rustfmt::skip]
+        let quad = device.create_buffer(&[
+            QuadVertex::new(-1.0, -1.0, 0.0, 1.0),
+            QuadVertex::new( 1.0, -1.0, 1.0, 1.0),
+            QuadVertex::new( 1.0,  1.0, 1.0, 0.0),
+            QuadVertex::new(-1.0, -1.0, 0.0, 1.0),
+            QuadVertex::new( 1.0,  1.0, 1.0, 0.0),
+            QuadVertex::new(-1.0,  1.0, 0.0, 0.0),
+        ]);
+
+        Self {
+            vs: device.create_shader(
+                "mipmap vertex shader",
+                include_bytes!("data/mipmap.vert.spv"),
+                ShaderStage::Vertex,
+            ),
+            fs: device.create_shader(
+                "mipmap fragment shader",
+                include_bytes!("data/mipmap.frag.spv"),
+                ShaderStage::Fragment,
+            ),
+            sampler: device
+                .create_sampler_with(&SamplerDescriptor::new(Filter::Linear, Filter::Linear)),
+            quad,
+            pipelines: Vec::new(),
+        }
+    }
+
+    /// Create the pipeline rendering to textures of the given format if it
+    /// doesn't exist yet, and return its index in `pipelines`.
+    fn prepare(&mut self, device: &Device, format: TextureFormat) -> usize {
+        match self.pipelines.iter().position(|(f, _)| *f == format) {
+            Some(index) => index,
+            None => {
+                let pipeline = self.create_pipeline(device, format);
+                self.pipelines.push((format, pipeline));
+                self.pipelines.len() - 1
+            }
+        }
+    }
+
+    fn create_pipeline(&self, device: &Device, format: TextureFormat) -> Pipeline {
+        let layout = device.create_pipeline_layout(&[Set(&[
+            Binding {
+                binding: BindingType::SampledTexture,
+                stage: ShaderStage::Fragment,
+            },
+            Binding {
+                binding: BindingType::Sampler,
+                stage: ShaderStage::Fragment,
+            },
+        ])]);
+
+        device.create_pipeline(
+            layout,
+            VertexState {
+                layout: VertexLayout::from(QuadVertex::FORMATS),
+                slots: Vec::new(),
+                index_format: IndexFormat::Uint16,
+            },
+            TargetState {
+                colors: vec![ColorTarget::new(format, Blending::constant())],
+                samples: 1,
+                depth_stencil: None,
+            },
+            PrimitiveState::default(),
+            ShaderStages {
+                vertex: &self.vs,
+                fragment: &self.fs,
+                entry_points: EntryPoints::MAIN,
+            },
+        )
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 /// Pipeline Bindings
 ///////////////////////////////////////////////////////////////////////////////
 
 /// A binding type.
+#[derive(Debug)]
 pub enum BindingType {
     UniformBuffer,
     UniformBufferDynamic,
+    /// A [`StorageBuffer`] shaders can read from and write to.
+    StorageBuffer,
+    /// A [`StorageBuffer`] shaders can only read from.
+    ReadonlyStorageBuffer,
     Sampler,
     SampledTexture,
+    /// A multisampled texture, see [`Framebuffer::multisample`].
+    SampledTextureMultisampled,
+    /// A texture with several layers, see [`Device::create_texture_array`].
+    SampledTextureArray,
+    /// A texture shaders can write to, see [`Device::create_storage_texture`]
+    /// and [`Device::create_storage_framebuffer`].
+    StorageTexture,
 }
 
 impl BindingType {
@@ -946,10 +2066,29 @@ impl BindingType {
         match self {
             BindingType::UniformBuffer => wgpu::BindingType::UniformBuffer { dynamic: false },
             BindingType::UniformBufferDynamic => wgpu::BindingType::UniformBuffer { dynamic: true },
+            Self::StorageBuffer => wgpu::BindingType::StorageBuffer {
+                dynamic: false,
+                readonly: false,
+            },
+            Self::ReadonlyStorageBuffer => wgpu::BindingType::StorageBuffer {
+                dynamic: false,
+                readonly: true,
+            },
             BindingType::SampledTexture => wgpu::BindingType::SampledTexture {
                 multisampled: false,
                 dimension: wgpu::TextureViewDimension::D2,
             },
+            Self::SampledTextureMultisampled => wgpu::BindingType::SampledTexture {
+                multisampled: true,
+                dimension: wgpu::TextureViewDimension::D2,
+            },
+            Self::SampledTextureArray => wgpu::BindingType::SampledTexture {
+                multisampled: false,
+                dimension: wgpu::TextureViewDimension::D2Array,
+            },
+            Self::StorageTexture => wgpu::BindingType::StorageTexture {
+                dimension: wgpu::TextureViewDimension::D2,
+            },
             BindingType::Sampler => wgpu::BindingType::Sampler,
         }
     }
@@ -969,6 +2108,12 @@ pub struct Pipeline {
 
     pub layout: PipelineLayout,
     pub vertex_layout: VertexLayout,
+    /// The layouts of the vertex buffers bound at slots `1` and up.
+    pub vertex_slots: Vec<VertexLayout>,
+
+    /// The vertex inputs of each slot, shared with the passes the pipeline
+    /// is set on.
+    inputs: Arc<[VertexInput]>,
 }
 
 impl<'a> AbstractPipeline<'a> for Pipeline {
@@ -978,9 +2123,17 @@ impl<'a> AbstractPipeline<'a> for Pipeline {
     fn description() -> PipelineDescription<'a> {
         PipelineDescription {
             vertex_layout: &[],
+            vertex_stride: None,
+            vertex_slots: &[],
             pipeline_layout: &[],
             vertex_shader: &[],
             fragment_shader: &[],
+            format: SwapChain::FORMAT,
+            index_format: IndexFormat::Uint16,
+            targets: &[],
+            depth_stencil: None,
+            primitive: PrimitiveState::default(),
+            entry_points: EntryPoints::MAIN,
         }
     }
 
@@ -990,6 +2143,7 @@ impl<'a> AbstractPipeline<'a> for Pipeline {
 
     fn apply(&self, pass: &mut Pass) {
         pass.wgpu.set_pipeline(&self.wgpu);
+        pass.vertex_inputs = Some(self.inputs.clone());
     }
 
     fn width(&self) -> u32 {
@@ -1015,7 +2169,7 @@ pub struct Blending {
 }
 
 impl Blending {
-    pub fn new(src_factor: BlendFactor, dst_factor: BlendFactor, operation: BlendOp) -> Self {
+    pub const fn new(src_factor: BlendFactor, dst_factor: BlendFactor, operation: BlendOp) -> Self {
         Blending {
             src_factor,
             dst_factor,
@@ -1023,7 +2177,7 @@ impl Blending {
         }
     }
 
-    pub fn constant() -> Self {
+    pub const fn constant() -> Self {
         Blending {
             src_factor: BlendFactor::One,
             dst_factor: BlendFactor::Zero,
@@ -1031,12 +2185,12 @@ impl Blending {
         }
     }
 
-    fn to_wgpu(&self) -> (wgpu::BlendFactor, wgpu::BlendFactor, wgpu::BlendOperation) {
-        (
-            self.src_factor.to_wgpu(),
-            self.dst_factor.to_wgpu(),
-            self.operation.to_wgpu(),
-        )
+    fn to_wgpu(&self) -> wgpu::BlendDescriptor {
+        wgpu::BlendDescriptor {
+            src_factor: self.src_factor.to_wgpu(),
+            dst_factor: self.dst_factor.to_wgpu(),
+            operation: self.operation.to_wgpu(),
+        }
     }
 }
 
@@ -1106,127 +2260,730 @@ pub trait AbstractPipeline<'a> {
 
 pub struct PipelineDescription<'a> {
     pub vertex_layout: &'a [VertexFormat],
+    /// The size of a vertex, including padding, if it isn't the size of
+    /// `vertex_layout`. Usually the size of the pipeline's [`Vertex`] type.
+    pub vertex_stride: Option<usize>,
+    /// Additional vertex buffer slots, eg. for per-instance data. Their
+    /// attributes follow the ones of `vertex_layout` in shader locations.
+    pub vertex_slots: &'a [VertexSlot<'a>],
     pub pipeline_layout: &'a [Set<'a>],
     pub vertex_shader: &'static [u8],
     pub fragment_shader: &'static [u8],
+    /// The format of the render target.
+    pub format: TextureFormat,
+    /// The format of index buffers drawn with this pipeline.
+    pub index_format: IndexFormat,
+    /// The color targets, written to by fragment shader outputs at locations
+    /// `0` and up, eg. for [`Frame::pass_with_targets`]. If empty, the pipeline
+    /// has a single target of `format`, blended as given when the pipeline is
+    /// created, which writes all channels.
+    pub targets: &'a [ColorTarget],
+    /// The depth and stencil state, if the pipeline renders to passes with a
+    /// [`DepthBuffer`].
+    pub depth_stencil: Option<DepthStencilState>,
+    /// How vertices are assembled into primitives, and how those are rasterized.
+    pub primitive: PrimitiveState,
+    /// The names of the shaders' entry points.
+    pub entry_points: EntryPoints<'a>,
 }
 
-///////////////////////////////////////////////////////////////////////////////
-/// Frame
-///////////////////////////////////////////////////////////////////////////////
-
-pub struct Frame {
-    encoder: wgpu::CommandEncoder,
+/// A color target of a pipeline, with its own format, blending and write mask.
+///
+/// Pipelines without [`PipelineDescription::targets`] have a single target,
+/// built from the description's format and the blending the pipeline is
+/// created with. Since [`PipelineDescription::targets`] is a slice, color
+/// targets are usually declared as a `const`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorTarget {
+    pub format: TextureFormat,
+    pub blending: Blending,
+    pub write_mask: ColorWrite,
 }
 
-impl Frame {
-    pub fn new(encoder: wgpu::CommandEncoder) -> Self {
-        Self { encoder }
-    }
-
-    pub fn pass<T: TextureView>(&mut self, op: PassOp, view: &T) -> Pass {
-        Pass::begin(&mut self.encoder, &view.texture_view(), op)
+impl ColorTarget {
+    /// A color target with all channels written to.
+    pub const fn new(format: TextureFormat, blending: Blending) -> Self {
+        Self {
+            format,
+            blending,
+            write_mask: ColorWrite::ALL,
+        }
     }
 
-    pub fn copy(&mut self, src: &UniformBuffer, dst: &UniformBuffer) {
-        self.encoder.copy_buffer_to_buffer(
-            &src.wgpu,
-            0,
-            &dst.wgpu,
-            0,
-            (src.size * src.count) as wgpu::BufferAddress,
-        );
+    fn to_wgpu(&self) -> wgpu::ColorStateDescriptor {
+        wgpu::ColorStateDescriptor {
+            format: self.format.to_wgpu(),
+            color_blend: self.blending.to_wgpu(),
+            alpha_blend: self.blending.to_wgpu(),
+            write_mask: self.write_mask.to_wgpu(),
+        }
     }
 }
 
-///////////////////////////////////////////////////////////////////////////////
-/// Pass
-///////////////////////////////////////////////////////////////////////////////
+/// A set of color channels written to by a [`ColorTarget`], eg.
+/// `ColorWrite::RED | ColorWrite::ALPHA`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ColorWrite(wgpu::ColorWrite);
+
+impl ColorWrite {
+    pub const NONE: Self = Self(wgpu::ColorWrite::empty());
+    pub const RED: Self = Self(wgpu::ColorWrite::RED);
+    pub const GREEN: Self = Self(wgpu::ColorWrite::GREEN);
+    pub const BLUE: Self = Self(wgpu::ColorWrite::BLUE);
+    pub const ALPHA: Self = Self(wgpu::ColorWrite::ALPHA);
+    pub const COLOR: Self = Self(wgpu::ColorWrite::COLOR);
+    pub const ALL: Self = Self(wgpu::ColorWrite::ALL);
+
+    /// Whether all channels of `other` are written to.
+    pub fn contains(self, other: Self) -> bool {
+        self.0.contains(other.0)
+    }
 
-pub struct Pass<'a> {
-    wgpu: wgpu::RenderPass<'a>,
+    fn to_wgpu(self) -> wgpu::ColorWrite {
+        self.0
+    }
 }
 
-impl<'a> Pass<'a> {
-    pub fn begin(
-        encoder: &'a mut wgpu::CommandEncoder,
-        view: &wgpu::TextureView,
-        op: PassOp,
-    ) -> Self {
-        let pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                attachment: &view,
-                load_op: op.to_wgpu(),
-                store_op: wgpu::StoreOp::Store,
-                clear_color: match op {
-                    PassOp::Clear(color) => color.to_wgpu(),
-                    PassOp::Load() => Rgba::TRANSPARENT.to_wgpu(),
-                },
-                resolve_target: None,
-            }],
-            depth_stencil_attachment: None,
-        });
-        Pass { wgpu: pass }
-    }
-    pub fn set_pipeline<T>(&mut self, pipeline: &T)
-    where
-        T: AbstractPipeline<'a>,
-    {
-        pipeline.apply(self);
-    }
-    pub fn set_binding(&mut self, group: &BindingGroup, offsets: &[u64]) {
-        self.wgpu
-            .set_bind_group(group.set_index, &group.wgpu, offsets);
-    }
-    pub fn set_index_buffer(&mut self, index_buf: &IndexBuffer) {
-        self.wgpu.set_index_buffer(&index_buf.wgpu, 0)
-    }
-    pub fn set_vertex_buffer(&mut self, vertex_buf: &VertexBuffer) {
-        self.wgpu.set_vertex_buffers(0, &[(&vertex_buf.wgpu, 0)])
-    }
-    pub fn draw<T: Draw>(&mut self, drawable: &T, binding: &BindingGroup) {
-        drawable.draw(binding, self);
-    }
-    pub fn draw_buffer(&mut self, buf: &VertexBuffer) {
-        self.set_vertex_buffer(buf);
-        self.wgpu.draw(0..buf.size, 0..1);
-    }
-    pub fn draw_buffer_range(&mut self, buf: &VertexBuffer, range: Range<u32>) {
-        self.set_vertex_buffer(buf);
-        self.wgpu.draw(range, 0..1);
+impl Default for ColorWrite {
+    fn default() -> Self {
+        Self::ALL
     }
-    pub fn draw_indexed(&mut self, indices: Range<u32>, instances: Range<u32>) {
-        self.wgpu.draw_indexed(indices, 0, instances)
+}
+
+impl std::ops::BitOr for ColorWrite {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
     }
 }
 
-pub enum PassOp {
-    Clear(Rgba),
-    Load(),
+/// How vertices are assembled into primitives.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PrimitiveTopology {
+    PointList,
+    LineList,
+    LineStrip,
+    TriangleList,
+    TriangleStrip,
 }
 
-impl PassOp {
-    fn to_wgpu(&self) -> wgpu::LoadOp {
+impl PrimitiveTopology {
+    fn to_wgpu(self) -> wgpu::PrimitiveTopology {
         match self {
-            PassOp::Clear(_) => wgpu::LoadOp::Clear,
-            PassOp::Load() => wgpu::LoadOp::Load,
+            Self::PointList => wgpu::PrimitiveTopology::PointList,
+            Self::LineList => wgpu::PrimitiveTopology::LineList,
+            Self::LineStrip => wgpu::PrimitiveTopology::LineStrip,
+            Self::TriangleList => wgpu::PrimitiveTopology::TriangleList,
+            Self::TriangleStrip => wgpu::PrimitiveTopology::TriangleStrip,
         }
     }
 }
 
-///////////////////////////////////////////////////////////////////////////////
-/// SwapChain & TextureView
-///////////////////////////////////////////////////////////////////////////////
+/// The vertex winding order of front-facing triangles.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FrontFace {
+    Ccw,
+    Cw,
+}
 
-pub trait TextureView {
-    fn texture_view(&self) -> &wgpu::TextureView;
+impl FrontFace {
+    fn to_wgpu(self) -> wgpu::FrontFace {
+        match self {
+            Self::Ccw => wgpu::FrontFace::Ccw,
+            Self::Cw => wgpu::FrontFace::Cw,
+        }
+    }
 }
 
-pub struct SwapChainTexture<'a>(wgpu::SwapChainOutput<'a>);
+/// Which triangles are discarded, if any.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CullMode {
+    None,
+    Front,
+    Back,
+}
 
-impl TextureView for SwapChainTexture<'_> {
-    fn texture_view(&self) -> &wgpu::TextureView {
-        &self.0.view
+impl CullMode {
+    fn to_wgpu(self) -> wgpu::CullMode {
+        match self {
+            Self::None => wgpu::CullMode::None,
+            Self::Front => wgpu::CullMode::Front,
+            Self::Back => wgpu::CullMode::Back,
+        }
+    }
+}
+
+/// A depth offset added to rasterized primitives, eg. to draw outlines over
+/// coplanar geometry without depth fighting.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct DepthBias {
+    pub constant: i32,
+    pub slope_scale: f32,
+    pub clamp: f32,
+}
+
+/// The primitive assembly and rasterization state of a pipeline.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PrimitiveState {
+    pub topology: PrimitiveTopology,
+    pub front_face: FrontFace,
+    pub cull_mode: CullMode,
+    pub depth_bias: DepthBias,
+}
+
+impl PrimitiveState {
+    /// Primitives of the given topology, with counter-clockwise front faces,
+    /// no culling and no depth bias.
+    pub const fn new(topology: PrimitiveTopology) -> Self {
+        Self {
+            topology,
+            front_face: FrontFace::Ccw,
+            cull_mode: CullMode::None,
+            depth_bias: DepthBias {
+                constant: 0,
+                slope_scale: 0.,
+                clamp: 0.,
+            },
+        }
+    }
+
+    pub fn front_face(mut self, front_face: FrontFace) -> Self {
+        self.front_face = front_face;
+        self
+    }
+
+    pub fn cull_mode(mut self, cull_mode: CullMode) -> Self {
+        self.cull_mode = cull_mode;
+        self
+    }
+
+    pub fn depth_bias(mut self, depth_bias: DepthBias) -> Self {
+        self.depth_bias = depth_bias;
+        self
+    }
+
+    fn to_wgpu(self) -> wgpu::RasterizationStateDescriptor {
+        wgpu::RasterizationStateDescriptor {
+            front_face: self.front_face.to_wgpu(),
+            cull_mode: self.cull_mode.to_wgpu(),
+            depth_bias: self.depth_bias.constant,
+            depth_bias_slope_scale: self.depth_bias.slope_scale,
+            depth_bias_clamp: self.depth_bias.clamp,
+        }
+    }
+}
+
+impl Default for PrimitiveState {
+    fn default() -> Self {
+        Self::new(PrimitiveTopology::TriangleList)
+    }
+}
+
+/// The names of the entry points of a pipeline's shaders.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EntryPoints<'a> {
+    pub vertex: &'a str,
+    pub fragment: &'a str,
+}
+
+impl EntryPoints<'static> {
+    /// The `main` function of each shader, as compiled from GLSL.
+    pub const MAIN: Self = Self {
+        vertex: "main",
+        fragment: "main",
+    };
+}
+
+/// The vertex input state a pipeline is created for.
+struct VertexState {
+    layout: VertexLayout,
+    slots: Vec<VertexLayout>,
+    index_format: IndexFormat,
+}
+
+/// The shaders a pipeline is created with.
+struct ShaderStages<'a> {
+    vertex: &'a Shader,
+    fragment: &'a Shader,
+    entry_points: EntryPoints<'a>,
+}
+
+/// The state of the render targets a pipeline is created for.
+struct TargetState {
+    colors: Vec<ColorTarget>,
+    samples: u32,
+    depth_stencil: Option<DepthStencilState>,
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Frame
+///////////////////////////////////////////////////////////////////////////////
+
+pub struct Frame {
+    encoder: wgpu::CommandEncoder,
+    /// Staging chunks written to by this frame's uploads. They are handed
+    /// back to the device's staging belt when the frame is submitted.
+    staged: Vec<Chunk>,
+}
+
+impl Frame {
+    pub fn new(encoder: wgpu::CommandEncoder) -> Self {
+        Self {
+            encoder,
+            staged: Vec::new(),
+        }
+    }
+
+    pub fn pass<T: TextureView>(&mut self, op: PassOp, view: &T) -> Pass {
+        match view.multisample_view() {
+            Some(msaa) => {
+                Pass::begin_resolved(&mut self.encoder, msaa, Some(view.texture_view()), op)
+            }
+            None => Pass::begin(&mut self.encoder, view.texture_view(), op),
+        }
+    }
+
+    /// Begin a pass with a depth/stencil buffer attached. Pipelines used in
+    /// the pass must be created with a matching [`DepthStencilState`].
+    pub fn pass_with_depth<T: TextureView>(
+        &mut self,
+        op: PassOp,
+        view: &T,
+        depth: &DepthBuffer,
+        depth_op: DepthStencilOp,
+    ) -> Pass<'_> {
+        self.pass_with_targets(&[(op, view as &dyn TextureView)], Some((depth, depth_op)))
+    }
+
+    /// Begin a pass with several color targets, and optionally a depth/stencil
+    /// buffer. Pipelines used in the pass must describe all of its targets in
+    /// [`PipelineDescription::targets`].
+    pub fn pass_with_targets(
+        &mut self,
+        targets: &[(PassOp, &dyn TextureView)],
+        depth: Option<(&DepthBuffer, DepthStencilOp)>,
+    ) -> Pass<'_> {
+        let colors = targets
+            .iter()
+            .map(|(op, view)| match view.multisample_view() {
+                Some(msaa) => (msaa, Some(view.texture_view()), *op),
+                None => (view.texture_view(), None, *op),
+            })
+            .collect::<Vec<_>>();
+
+        Pass::begin_with(
+            &mut self.encoder,
+            colors.as_slice(),
+            depth.map(|(buf, op)| (&buf.view, op)),
+        )
+    }
+
+    pub fn compute_pass(&mut self) -> ComputePass<'_> {
+        ComputePass::begin(&mut self.encoder)
+    }
+
+    pub fn copy(&mut self, src: &UniformBuffer, dst: &UniformBuffer) {
+        self.encoder.copy_buffer_to_buffer(
+            &src.wgpu,
+            0,
+            &dst.wgpu,
+            0,
+            (src.size * src.count) as wgpu::BufferAddress,
+        );
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Pass
+///////////////////////////////////////////////////////////////////////////////
+
+pub struct Pass<'a> {
+    wgpu: wgpu::RenderPass<'a>,
+    /// The vertex inputs of the current pipeline's slots.
+    vertex_inputs: Option<Arc<[VertexInput]>>,
+    /// The vertex inputs of the buffers bound to each slot.
+    vertex_buffers: Vec<Option<VertexInput>>,
+}
+
+impl<'a> Pass<'a> {
+    pub fn begin(
+        encoder: &'a mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        op: PassOp,
+    ) -> Self {
+        Self::begin_resolved(encoder, view, None, op)
+    }
+
+    /// Begin a pass rendering to `view`, which is resolved into `resolve_target`
+    /// at the end of the pass if given.
+    pub fn begin_resolved(
+        encoder: &'a mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        resolve_target: Option<&wgpu::TextureView>,
+        op: PassOp,
+    ) -> Self {
+        Self::begin_with(encoder, &[(view, resolve_target, op)], None)
+    }
+
+    /// Begin a pass with the given color attachments, each with an optional
+    /// resolve target, and an optional depth/stencil attachment.
+    fn begin_with(
+        encoder: &'a mut wgpu::CommandEncoder,
+        colors: &[(&wgpu::TextureView, Option<&wgpu::TextureView>, PassOp)],
+        depth: Option<(&wgpu::TextureView, DepthStencilOp)>,
+    ) -> Self {
+        let color_attachments = colors
+            .iter()
+            .map(
+                |&(attachment, resolve_target, op)| wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment,
+                    load_op: op.to_wgpu(),
+                    store_op: wgpu::StoreOp::Store,
+                    clear_color: match op {
+                        PassOp::Clear(color) => color.to_wgpu(),
+                        PassOp::Load() => Rgba::TRANSPARENT.to_wgpu(),
+                    },
+                    resolve_target,
+                },
+            )
+            .collect::<Vec<_>>();
+
+        let pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: color_attachments.as_slice(),
+            depth_stencil_attachment: depth.map(|(attachment, op)| op.to_wgpu(attachment)),
+        });
+        Pass {
+            wgpu: pass,
+            vertex_inputs: None,
+            vertex_buffers: Vec::new(),
+        }
+    }
+    pub fn set_pipeline<T>(&mut self, pipeline: &T)
+    where
+        T: AbstractPipeline<'a>,
+    {
+        pipeline.apply(self);
+    }
+    pub fn set_binding(&mut self, group: &BindingGroup, offsets: &[u64]) {
+        self.wgpu
+            .set_bind_group(group.set_index, &group.wgpu, offsets);
+    }
+    /// Bind a group whose only dynamic binding is `buf`, selecting the entry
+    /// at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds, see [`DynamicBuffer::offset`].
+    pub fn set_dynamic_binding<T>(
+        &mut self,
+        group: &BindingGroup,
+        buf: &DynamicBuffer<T>,
+        index: usize,
+    ) {
+        self.set_binding(group, &[buf.offset(index)]);
+    }
+    pub fn set_index_buffer(&mut self, index_buf: &IndexBuffer) {
+        self.wgpu.set_index_buffer(&index_buf.wgpu, 0)
+    }
+    pub fn set_stencil_reference(&mut self, reference: u32) {
+        self.wgpu.set_stencil_reference(reference)
+    }
+    pub fn set_vertex_buffer<V: Vertex>(&mut self, vertex_buf: &VertexBuffer<V>) {
+        self.set_vertex_buffer_at(0, vertex_buf)
+    }
+    /// Bind a vertex buffer to the given slot, see [`PipelineDescription::vertex_slots`].
+    ///
+    /// Buffers can be bound before or after the pipeline they are drawn with.
+    /// They are checked against it when drawing, see [`Pass::try_draw_instanced`].
+    pub fn set_vertex_buffer_at<V: Vertex>(&mut self, slot: u32, vertex_buf: &VertexBuffer<V>) {
+        let slot = slot as usize;
+
+        if self.vertex_buffers.len() <= slot {
+            self.vertex_buffers.resize_with(slot + 1, || None);
+        }
+        self.vertex_buffers[slot] = Some(VertexInput::of::<V>());
+        self.wgpu
+            .set_vertex_buffers(slot as u32, &[(&vertex_buf.wgpu, 0)]);
+    }
+    pub fn draw<T: Draw>(&mut self, drawable: &T, binding: &BindingGroup) {
+        drawable.draw(binding, self);
+    }
+    pub fn draw_buffer<V: Vertex>(&mut self, buf: &VertexBuffer<V>) {
+        self.set_vertex_buffer(buf);
+        self.draw_instanced(0..buf.size, 0..1);
+    }
+    pub fn draw_buffer_range<V: Vertex>(&mut self, buf: &VertexBuffer<V>, range: Range<u32>) {
+        self.set_vertex_buffer(buf);
+        self.draw_instanced(range, 0..1);
+    }
+    /// Draw with the bound vertex buffers. Panics if they don't match the
+    /// current pipeline, see [`Pass::try_draw_instanced`].
+    pub fn draw_instanced(&mut self, vertices: Range<u32>, instances: Range<u32>) {
+        self.try_draw_instanced(vertices, instances)
+            .unwrap_or_else(|err| panic!("fatal: {}", err))
+    }
+    /// Draw with the bound vertex buffers.
+    ///
+    /// Fails without drawing anything if a vertex slot of the current pipeline
+    /// has no buffer bound, or one whose vertex formats or stride don't match
+    /// the pipeline's.
+    pub fn try_draw_instanced(
+        &mut self,
+        vertices: Range<u32>,
+        instances: Range<u32>,
+    ) -> Result<(), Error> {
+        self.check_vertex_buffers()?;
+        self.wgpu.draw(vertices, instances);
+
+        Ok(())
+    }
+    /// Draw one instance of `buf` per element of `instances`, which is bound
+    /// to slot `1`.
+    pub fn draw_buffer_instanced<V: Vertex, I: Vertex>(
+        &mut self,
+        buf: &VertexBuffer<V>,
+        instances: &VertexBuffer<I>,
+    ) {
+        self.set_vertex_buffer(buf);
+        self.set_vertex_buffer_at(1, instances);
+        self.draw_instanced(0..buf.size, 0..instances.size);
+    }
+    /// Draw with the bound index and vertex buffers. Panics if the vertex
+    /// buffers don't match the current pipeline, see [`Pass::try_draw_indexed`].
+    pub fn draw_indexed(&mut self, indices: Range<u32>, instances: Range<u32>) {
+        self.try_draw_indexed(indices, instances)
+            .unwrap_or_else(|err| panic!("fatal: {}", err))
+    }
+    /// Draw with the bound index and vertex buffers. Fails without drawing
+    /// anything if the vertex buffers don't match the current pipeline, see
+    /// [`Pass::try_draw_instanced`].
+    pub fn try_draw_indexed(
+        &mut self,
+        indices: Range<u32>,
+        instances: Range<u32>,
+    ) -> Result<(), Error> {
+        self.check_vertex_buffers()?;
+        self.wgpu.draw_indexed(indices, 0, instances);
+
+        Ok(())
+    }
+    pub fn draw_mesh<V: Vertex>(&mut self, mesh: &Mesh<V>) {
+        self.set_vertex_buffer(&mesh.vertices);
+        self.set_index_buffer(&mesh.indices);
+        self.draw_indexed(0..mesh.indices.size, 0..1);
+    }
+    /// Draw one instance of `mesh` per element of `instances`, which is bound
+    /// to slot `1`.
+    pub fn draw_mesh_instanced<V: Vertex, I: Vertex>(
+        &mut self,
+        mesh: &Mesh<V>,
+        instances: &VertexBuffer<I>,
+    ) {
+        self.set_vertex_buffer(&mesh.vertices);
+        self.set_vertex_buffer_at(1, instances);
+        self.set_index_buffer(&mesh.indices);
+        self.draw_indexed(0..mesh.indices.size, 0..instances.size);
+    }
+
+    /// Check the bound vertex buffers against the vertex slots of the current
+    /// pipeline. Slots without vertex attributes don't need a buffer.
+    fn check_vertex_buffers(&self) -> Result<(), Error> {
+        let inputs = match &self.vertex_inputs {
+            Some(inputs) => inputs,
+            None => return Ok(()),
+        };
+        for (slot, expected) in inputs.iter().enumerate() {
+            if expected.formats.is_empty() {
+                continue;
+            }
+            match self.vertex_buffers.get(slot).and_then(Option::as_ref) {
+                Some(bound) if bound == expected => {}
+                Some(bound) => {
+                    return Err(Error::VertexFormatMismatch(format!(
+                        "vertex buffer at slot {} has formats {:?} and a stride of {} bytes, \
+                         but the pipeline expects {:?} and a stride of {} bytes",
+                        slot, bound.formats, bound.stride, expected.formats, expected.stride
+                    )))
+                }
+                None => {
+                    return Err(Error::VertexFormatMismatch(format!(
+                        "no vertex buffer is bound at slot {}",
+                        slot
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How the color target of a pass is initialized.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PassOp {
+    Clear(Rgba),
+    /// Keep the contents of the target.
+    ///
+    /// Multisampled targets are only resolved into their texture, never the
+    /// other way around: loading one loads the samples left by the last pass
+    /// rendering to it, and doesn't see anything written to the resolved
+    /// texture since, eg. by [`Canvas`] operations or compute shaders. For
+    /// multisampled swap chains, that is the previous frame.
+    Load(),
+}
+
+impl PassOp {
+    fn to_wgpu(&self) -> wgpu::LoadOp {
+        match self {
+            PassOp::Clear(_) => wgpu::LoadOp::Clear,
+            PassOp::Load() => wgpu::LoadOp::Load,
+        }
+    }
+}
+
+/// How the depth/stencil buffer of a pass is initialized.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DepthStencilOp {
+    /// Clear the depth and stencil values to the given ones.
+    Clear(f32, u32),
+    Load(),
+}
+
+impl DepthStencilOp {
+    /// Clear the depth to the far plane, and the stencil to zero.
+    pub const CLEAR: Self = Self::Clear(1.0, 0);
+
+    fn to_wgpu(
+        self,
+        attachment: &wgpu::TextureView,
+    ) -> wgpu::RenderPassDepthStencilAttachmentDescriptor<&wgpu::TextureView> {
+        let (load_op, clear_depth, clear_stencil) = match self {
+            Self::Clear(depth, stencil) => (wgpu::LoadOp::Clear, depth, stencil),
+            Self::Load() => (wgpu::LoadOp::Load, 1.0, 0),
+        };
+        wgpu::RenderPassDepthStencilAttachmentDescriptor {
+            attachment,
+            depth_load_op: load_op,
+            depth_store_op: wgpu::StoreOp::Store,
+            clear_depth,
+            stencil_load_op: load_op,
+            stencil_store_op: wgpu::StoreOp::Store,
+            clear_stencil,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Compute
+///////////////////////////////////////////////////////////////////////////////
+
+pub struct ComputePipeline {
+    wgpu: wgpu::ComputePipeline,
+
+    pub layout: PipelineLayout,
+}
+
+impl<'a> AbstractComputePipeline<'a> for ComputePipeline {
+    type PrepareContext = ();
+    type Uniforms = ();
+
+    fn description() -> ComputePipelineDescription<'a> {
+        ComputePipelineDescription {
+            pipeline_layout: &[],
+            compute_shader: &[],
+            entry_point: "main",
+        }
+    }
+
+    fn setup(pipeline: Self, _dev: &Device) -> Self {
+        pipeline
+    }
+
+    fn apply(&self, pass: &mut ComputePass) {
+        pass.wgpu.set_pipeline(&self.wgpu);
+    }
+
+    fn prepare(&'a self, _unused: ()) -> Option<(&'a UniformBuffer, Vec<()>)> {
+        None
+    }
+}
+
+/// The compute counterpart of [`AbstractPipeline`]. Compute pipelines are
+/// created with [`Renderer::compute_pipeline`], and used in a [`ComputePass`].
+pub trait AbstractComputePipeline<'a> {
+    type PrepareContext;
+    type Uniforms: Copy + 'static;
+
+    fn description() -> ComputePipelineDescription<'a>;
+    fn setup(pip: ComputePipeline, dev: &Device) -> Self;
+    fn apply(&self, pass: &mut ComputePass);
+    fn prepare(
+        &'a self,
+        t: Self::PrepareContext,
+    ) -> Option<(&'a UniformBuffer, Vec<Self::Uniforms>)>;
+}
+
+pub struct ComputePipelineDescription<'a> {
+    pub pipeline_layout: &'a [Set<'a>],
+    pub compute_shader: &'static [u8],
+    /// The name of the compute shader's entry point, usually `main`.
+    pub entry_point: &'a str,
+}
+
+/// A compute pass, created with [`Frame::compute_pass`].
+pub struct ComputePass<'a> {
+    wgpu: wgpu::ComputePass<'a>,
+}
+
+impl<'a> ComputePass<'a> {
+    pub fn begin(encoder: &'a mut wgpu::CommandEncoder) -> Self {
+        Self {
+            wgpu: encoder.begin_compute_pass(),
+        }
+    }
+    pub fn set_pipeline<T>(&mut self, pipeline: &T)
+    where
+        T: AbstractComputePipeline<'a>,
+    {
+        pipeline.apply(self);
+    }
+    pub fn set_binding(&mut self, group: &BindingGroup, offsets: &[u64]) {
+        self.wgpu
+            .set_bind_group(group.set_index, &group.wgpu, offsets);
+    }
+    /// Dispatch `x * y * z` work groups. The size of a work group is
+    /// declared in the compute shader.
+    pub fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+        self.wgpu.dispatch(x, y, z);
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// SwapChain & TextureView
+///////////////////////////////////////////////////////////////////////////////
+
+pub trait TextureView {
+    fn texture_view(&self) -> &wgpu::TextureView;
+
+    /// The multisampled view to render to, if any. It is resolved into
+    /// [`TextureView::texture_view`] at the end of a pass.
+    fn multisample_view(&self) -> Option<&wgpu::TextureView> {
+        None
+    }
+}
+
+pub struct SwapChainTexture<'a> {
+    output: wgpu::SwapChainOutput<'a>,
+    msaa: Option<&'a wgpu::TextureView>,
+}
+
+impl TextureView for SwapChainTexture<'_> {
+    fn texture_view(&self) -> &wgpu::TextureView {
+        &self.output.view
+    }
+
+    fn multisample_view(&self) -> Option<&wgpu::TextureView> {
+        self.msaa
     }
 }
 
@@ -1251,36 +3008,171 @@ impl Default for PresentMode {
     }
 }
 
+/// A surface to present to, usually a window.
+///
+/// A renderer's main surface is created along with it, while additional
+/// surfaces can be created with [`Renderer::surface`].
+#[derive(Clone)]
+pub struct Surface {
+    wgpu: Arc<wgpu::Surface>,
+}
+
 /// A handle to a swap chain.
-/// 
+///
 /// A `SwapChain` represents the image or series of images that will be presented to a [`Renderer`].
 /// A `SwapChain` may be created with [`Renderer::swap_chain`].
+///
+/// Pipelines and framebuffers sized after the swap chain are resized by the
+/// caller when [`SwapChain::resize`] reports a new size.
 pub struct SwapChain {
-    pub width: u32,
-    pub height: u32,
-
-    wgpu: wgpu::SwapChain,
+    width: u32,
+    height: u32,
+
+    mode: PresentMode,
+    surface: Surface,
+    samples: u32,
+    msaa: Option<Multisample>,
+    /// The underlying swap chain, or `None` while the size is zero, which
+    /// wgpu doesn't support.
+    wgpu: Option<wgpu::SwapChain>,
 }
 
 impl SwapChain {
+    /// The texture format of swap chain textures. Pipelines rendering to a
+    /// swap chain should use this format.
+    pub const FORMAT: TextureFormat = TextureFormat::Bgra8Unorm;
+
+    fn new(
+        device: &Device,
+        surface: Surface,
+        width: u32,
+        height: u32,
+        mode: PresentMode,
+        samples: u32,
+    ) -> Self {
+        let mut chain = Self {
+            wgpu: None,
+            msaa: None,
+            surface,
+            mode,
+            samples,
+            width,
+            height,
+        };
+        chain.recreate(device);
+        chain
+    }
+
     /// Convenience method to retrieve `(width, height)`
     #[inline]
     pub fn size(&self) -> (u32, u32) {
         (self.width, self.height)
     }
-    
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        self.mode
+    }
+
+    /// The number of samples per pixel rendered to. Pipelines rendering to
+    /// this swap chain should be created with the same sample count, see
+    /// [`Renderer::pipeline_msaa`].
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Resize the swap chain, if its size differs from the given one. Returns
+    /// the new size if it was resized, so that pipelines and framebuffers
+    /// rendering to it can be resized with [`AbstractPipeline::resize`] and
+    /// [`Framebuffer::resize`]:
+    ///
+    /// ```ignore
+    /// if let Some((w, h)) = chain.resize(&r.device, w, h) {
+    ///     pipeline.resize(w, h);
+    /// }
+    /// ```
+    ///
+    /// A size of zero, as reported for minimized windows, releases the
+    /// underlying swap chain until a non-zero size is set, and returns `None`:
+    /// there is nothing to resize other resources to in the meantime.
+    pub fn resize(&mut self, device: &Device, w: u32, h: u32) -> Option<(u32, u32)> {
+        if self.size() == (w, h) {
+            return None;
+        }
+        self.width = w;
+        self.height = h;
+        self.recreate(device);
+
+        if self.is_empty() {
+            return None;
+        }
+        Some((w, h))
+    }
+
+    /// Whether the swap chain has a width or height of zero, in which case
+    /// there is nothing to draw to.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
     /// Returns the next texture to be presented by the swapchain for drawing.
-    /// 
+    ///
     /// When the [`SwapChainTexture`] returned by this method is dropped, the
     /// swapchain will present the texture to the associated [`Renderer`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the swap chain is empty. See [`SwapChain::try_next`].
     pub fn next(&mut self) -> SwapChainTexture {
-        SwapChainTexture(self.wgpu.get_next_texture())
+        self.try_next()
+            .unwrap_or_else(|err| panic!("fatal: {}", err))
+    }
+
+    /// Returns the next texture to be presented, or [`Error::EmptySwapChain`]
+    /// if the swap chain is empty, eg. while its window is minimized.
+    ///
+    /// If the surface was lost or became outdated, wgpu recreates the swap
+    /// chain with its current size and present mode before acquiring the
+    /// texture, so only changes in size need to be handled, via
+    /// [`SwapChain::resize`].
+    pub fn try_next(&mut self) -> Result<SwapChainTexture<'_>, Error> {
+        let wgpu = self.wgpu.as_mut().ok_or(Error::EmptySwapChain)?;
+
+        Ok(SwapChainTexture {
+            output: wgpu.get_next_texture(),
+            msaa: self.msaa.as_ref().map(|ms| &ms.view),
+        })
+    }
+
+    /// Recreate the underlying swap chain with the current size and present
+    /// mode, or release it if the size is zero.
+    fn recreate(&mut self, device: &Device) {
+        self.wgpu = None;
+        self.msaa = None;
+
+        if self.is_empty() {
+            return;
+        }
+        self.wgpu = Some(device.create_surface_swap_chain(
+            &self.surface,
+            self.width,
+            self.height,
+            self.mode,
+        ));
+        self.msaa = device.create_multisample(self.width, self.height, Self::FORMAT, self.samples);
     }
 
     fn descriptor(width: u32, height: u32, mode: PresentMode) -> wgpu::SwapChainDescriptor {
         wgpu::SwapChainDescriptor {
             usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
-            format: wgpu::TextureFormat::Bgra8Unorm,
+            format: Self::FORMAT.to_wgpu(),
             present_mode: mode.to_wgpu(),
             width,
             height,
@@ -1288,136 +3180,820 @@ impl SwapChain {
     }
 }
 
+///////////////////////////////////////////////////////////////////////////////
+/// Readback
+///////////////////////////////////////////////////////////////////////////////
+
+/// A conversion applied to texels read back into host memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conversion {
+    /// Return texels as they are stored in the texture.
+    None,
+    /// Swap the red and blue channels, eg. to turn BGRA framebuffer texels
+    /// into RGBA. Has no effect on textures that aren't in a BGRA format.
+    BgraToRgba,
+}
+
+/// The texels of a completed [`Readback`], or the error it failed with.
+type ReadbackResult = Result<Vec<u8>, Error>;
+
+/// A pending read of texture data into host memory, created with
+/// [`Renderer::read_texture`].
+pub struct Readback {
+    result: Arc<Mutex<Option<ReadbackResult>>>,
+    // Kept alive until the mapping completes.
+    #[allow(dead_code)]
+    buffer: wgpu::Buffer,
+}
+
+impl Readback {
+    /// Row pitches of texture copies must be a multiple of this.
+    const ROW_ALIGNMENT: u32 = 256;
+
+    fn new(
+        buffer: wgpu::Buffer,
+        bytesize: u64,
+        row: u32,
+        row_pitch: u32,
+        conversion: Conversion,
+    ) -> Self {
+        let result = Arc::new(Mutex::new(None));
+        let shared = result.clone();
+
+        buffer.map_read_async(
+            0,
+            bytesize,
+            move |mapping: wgpu::BufferMapAsyncResult<&[u8]>| {
+                let data = match mapping {
+                    Ok(ref mapping) => Ok(Self::unpad(mapping.data, row, row_pitch, conversion)),
+                    Err(ref err) => Err(Error::ReadbackFailed(format!(
+                        "failed to map buffer: {:?}",
+                        err
+                    ))),
+                };
+                *shared.lock().unwrap() = Some(data);
+            },
+        );
+
+        Self { result, buffer }
+    }
+
+    /// Check whether the data is available, without blocking. Returns the
+    /// data once, when it is ready.
+    pub fn poll(&self, device: &Device) -> Option<Result<Vec<u8>, Error>> {
+        device.device.poll(false);
+        self.result.lock().unwrap().take()
+    }
+
+    /// Block until the data is available and return it.
+    pub fn wait(self, device: &Device) -> Result<Vec<u8>, Error> {
+        device.device.poll(true);
+        self.result.lock().unwrap().take().unwrap_or_else(|| {
+            Err(Error::ReadbackFailed(
+                "buffer mapping did not complete".to_owned(),
+            ))
+        })
+    }
+
+    fn row_pitch(row: u32) -> u32 {
+        let align = Self::ROW_ALIGNMENT;
+        row.div_ceil(align) * align
+    }
+
+    /// Strip the row padding added to satisfy the copy alignment, and apply
+    /// the texel conversion.
+    fn unpad(data: &[u8], row: u32, row_pitch: u32, conversion: Conversion) -> Vec<u8> {
+        let (row, row_pitch) = (row as usize, row_pitch as usize);
+        let mut texels = Vec::with_capacity(data.len() / row_pitch * row);
+
+        for padded in data.chunks(row_pitch) {
+            texels.extend_from_slice(&padded[..row]);
+        }
+        if conversion == Conversion::BgraToRgba {
+            for texel in texels.chunks_mut(4) {
+                texel.swap(0, 2);
+            }
+        }
+        texels
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 /// Renderer
 ///////////////////////////////////////////////////////////////////////////////
 
 pub struct Renderer {
     pub device: Device,
+
+    /// Resources used to generate mipmaps, created on first use.
+    mipmapper: Option<Mipmapper>,
 }
 
 impl Renderer {
     pub fn new(window: RawWindowHandle) -> Self {
         Self {
             device: Device::new(window),
+            mipmapper: None,
         }
     }
 
-    pub fn swap_chain(&self, w: u32, h: u32, mode: PresentMode) -> SwapChain {
-        SwapChain {
-            wgpu: self.device.create_swap_chain(w, h, mode),
-            width: w,
-            height: h,
+    /// Create a renderer that isn't attached to any window. Such a renderer
+    /// can render to framebuffers and read them back, but cannot create
+    /// swap chains.
+    pub fn headless() -> Self {
+        Self {
+            device: Device::headless(),
+            mipmapper: None,
+        }
+    }
+
+    /// Create a renderer with the given device options.
+    pub fn with_options(window: RawWindowHandle, options: DeviceOptions) -> Self {
+        Self {
+            device: Device::with_options(Some(window), options),
+            mipmapper: None,
         }
     }
 
+    /// Create a headless renderer with the given device options.
+    pub fn headless_with_options(options: DeviceOptions) -> Self {
+        Self {
+            device: Device::with_options(None, options),
+            mipmapper: None,
+        }
+    }
+
+    /// Create a swap chain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the renderer is headless. See [`Renderer::try_swap_chain`].
+    pub fn swap_chain(&self, w: u32, h: u32, mode: PresentMode) -> SwapChain {
+        self.try_swap_chain(w, h, mode)
+            .unwrap_or_else(|err| panic!("fatal: {}", err))
+    }
+
+    /// Create a swap chain, or return [`Error::NoSurface`] if the renderer
+    /// is headless.
+    pub fn try_swap_chain(&self, w: u32, h: u32, mode: PresentMode) -> Result<SwapChain, Error> {
+        self.try_swap_chain_msaa(w, h, mode, 1)
+    }
+
+    /// Create a multisampled swap chain, rendering `samples` samples per pixel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the renderer is headless. See [`Renderer::try_swap_chain_msaa`].
+    pub fn swap_chain_msaa(&self, w: u32, h: u32, mode: PresentMode, samples: u32) -> SwapChain {
+        self.try_swap_chain_msaa(w, h, mode, samples)
+            .unwrap_or_else(|err| panic!("fatal: {}", err))
+    }
+
+    pub fn try_swap_chain_msaa(
+        &self,
+        w: u32,
+        h: u32,
+        mode: PresentMode,
+        samples: u32,
+    ) -> Result<SwapChain, Error> {
+        let surface = self.device.surface.clone().ok_or(Error::NoSurface)?;
+
+        Ok(SwapChain::new(&self.device, surface, w, h, mode, samples))
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.device.is_headless()
+    }
+
+    /// Create a surface for an additional window. Resources created by this
+    /// renderer, such as textures and pipelines, can be used with any of its
+    /// surfaces.
+    pub fn surface(&self, window: RawWindowHandle) -> Surface {
+        self.device.create_surface(window)
+    }
+
+    /// Create a swap chain for the given surface.
+    pub fn swap_chain_for(
+        &self,
+        surface: &Surface,
+        w: u32,
+        h: u32,
+        mode: PresentMode,
+    ) -> SwapChain {
+        self.swap_chain_for_msaa(surface, w, h, mode, 1)
+    }
+
+    /// Create a multisampled swap chain for the given surface, rendering
+    /// `samples` samples per pixel.
+    pub fn swap_chain_for_msaa(
+        &self,
+        surface: &Surface,
+        w: u32,
+        h: u32,
+        mode: PresentMode,
+        samples: u32,
+    ) -> SwapChain {
+        SwapChain::new(&self.device, surface.clone(), w, h, mode, samples)
+    }
+
     pub fn texture(&self, w: u32, h: u32) -> Texture {
         self.device.create_texture(w, h)
     }
 
+    pub fn texture_with_format(&self, w: u32, h: u32, format: TextureFormat) -> Texture {
+        self.device.create_texture_with_format(w, h, format)
+    }
+
+    /// Create a texture with a full mip chain, see [`Renderer::generate_mipmaps`].
+    pub fn texture_mipmapped(&self, w: u32, h: u32, format: TextureFormat) -> Texture {
+        self.device.create_mipmapped_texture(w, h, format)
+    }
+
+    /// Create a texture with several layers, see [`Device::create_texture_array`].
+    pub fn texture_array(&self, w: u32, h: u32, layers: u32, format: TextureFormat) -> Texture {
+        self.device.create_texture_array(w, h, layers, format)
+    }
+
+    /// Create a texture compute shaders can write to, see [`Device::create_storage_texture`].
+    pub fn storage_texture(&self, w: u32, h: u32, format: TextureFormat) -> Texture {
+        self.device.create_storage_texture(w, h, format)
+    }
+
     pub fn framebuffer(&self, w: u32, h: u32) -> Framebuffer {
         self.device.create_framebuffer(w, h)
     }
 
-    pub fn vertex_buffer<T>(&self, verts: &[T]) -> VertexBuffer
+    pub fn framebuffer_with_format(&self, w: u32, h: u32, format: TextureFormat) -> Framebuffer {
+        self.device.create_framebuffer_with_format(w, h, format)
+    }
+
+    /// Create a framebuffer compute shaders can write to, see
+    /// [`Device::create_storage_framebuffer`].
+    pub fn storage_framebuffer(&self, w: u32, h: u32, format: TextureFormat) -> Framebuffer {
+        self.device.create_storage_framebuffer(w, h, format)
+    }
+
+    /// Create a framebuffer rendering `samples` samples per texel.
+    pub fn framebuffer_msaa(&self, w: u32, h: u32, samples: u32) -> Framebuffer {
+        self.device.create_framebuffer_msaa(w, h, samples)
+    }
+
+    /// Create a depth/stencil buffer with the default format.
+    pub fn depth_buffer(&self, w: u32, h: u32) -> DepthBuffer {
+        self.device
+            .create_depth_buffer(w, h, DepthFormat::default(), 1)
+    }
+
+    /// Create a depth/stencil buffer of the given format and sample count.
+    pub fn depth_buffer_with(
+        &self,
+        w: u32,
+        h: u32,
+        format: DepthFormat,
+        samples: u32,
+    ) -> DepthBuffer {
+        self.device.create_depth_buffer(w, h, format, samples)
+    }
+
+    pub fn vertex_buffer<V: Vertex>(&self, verts: &[V]) -> VertexBuffer<V> {
+        self.device.create_buffer(verts)
+    }
+
+    pub fn try_vertex_buffer<V: Vertex>(&self, verts: &[V]) -> Result<VertexBuffer<V>, Error> {
+        self.device.try_create_buffer(verts)
+    }
+
+    pub fn index_buffer(&self, indices: &[u16]) -> IndexBuffer {
+        self.device.create_index(indices)
+    }
+
+    pub fn index_buffer_u32(&self, indices: &[u32]) -> IndexBuffer {
+        self.device.create_index_u32(indices)
+    }
+
+    /// Replace the vertices of a buffer, see [`Device::update_vertex_buffer`].
+    pub fn update_vertex_buffer<V: Vertex>(
+        &mut self,
+        buf: &mut VertexBuffer<V>,
+        verts: &[V],
+        f: &mut Frame,
+    ) {
+        self.device.update_vertex_buffer(verts, buf, f);
+    }
+
+    /// Overwrite part of a buffer's vertices, see [`Device::update_vertex_buffer_range`].
+    pub fn update_vertex_buffer_range<V: Vertex>(
+        &mut self,
+        buf: &VertexBuffer<V>,
+        offset: u32,
+        verts: &[V],
+        f: &mut Frame,
+    ) {
+        self.device
+            .update_vertex_buffer_range(offset, verts, buf, f);
+    }
+
+    /// Overwrite part of a buffer's vertices, see [`Device::try_update_vertex_buffer_range`].
+    pub fn try_update_vertex_buffer_range<V: Vertex>(
+        &mut self,
+        buf: &VertexBuffer<V>,
+        offset: u32,
+        verts: &[V],
+        f: &mut Frame,
+    ) -> Result<(), Error> {
+        self.device
+            .try_update_vertex_buffer_range(offset, verts, buf, f)
+    }
+
+    /// Replace the indices of a buffer, see [`Device::update_index_buffer`].
+    pub fn update_index_buffer<I: IndexType>(
+        &mut self,
+        buf: &mut IndexBuffer,
+        indices: &[I],
+        f: &mut Frame,
+    ) {
+        self.device.update_index_buffer(indices, buf, f);
+    }
+
+    /// Overwrite part of a buffer's indices, see [`Device::update_index_buffer_range`].
+    pub fn update_index_buffer_range<I: IndexType>(
+        &mut self,
+        buf: &IndexBuffer,
+        offset: u32,
+        indices: &[I],
+        f: &mut Frame,
+    ) {
+        self.device
+            .update_index_buffer_range(offset, indices, buf, f);
+    }
+
+    /// Overwrite part of a buffer's indices, see [`Device::try_update_index_buffer_range`].
+    pub fn try_update_index_buffer_range<I: IndexType>(
+        &mut self,
+        buf: &IndexBuffer,
+        offset: u32,
+        indices: &[I],
+        f: &mut Frame,
+    ) -> Result<(), Error> {
+        self.device
+            .try_update_index_buffer_range(offset, indices, buf, f)
+    }
+
+    /// Replace the vertices and indices of a mesh.
+    pub fn update_mesh<V, I>(
+        &mut self,
+        mesh: &mut Mesh<V>,
+        verts: &[V],
+        indices: &[I],
+        f: &mut Frame,
+    ) where
+        V: Vertex,
+        I: IndexType,
+    {
+        self.device
+            .update_vertex_buffer(verts, &mut mesh.vertices, f);
+        self.device
+            .update_index_buffer(indices, &mut mesh.indices, f);
+    }
+
+    pub fn uniform_buffer<T>(&self, buf: &[T]) -> UniformBuffer
+    where
+        T: 'static + Copy,
+    {
+        self.device.create_uniform_buffer(buf)
+    }
+
+    pub fn dynamic_buffer<T>(&self, entries: &[T]) -> DynamicBuffer<T>
+    where
+        T: 'static + Copy,
+    {
+        self.device.create_dynamic_buffer(entries)
+    }
+
+    pub fn storage_buffer<T>(&self, data: &[T]) -> StorageBuffer
+    where
+        T: 'static + Copy,
+    {
+        self.device.create_storage_buffer(data)
+    }
+
+    /// Write `data` to a [`StorageBuffer`] as part of the given frame.
+    pub fn update_storage_buffer<T>(&mut self, buf: &StorageBuffer, data: &[T], f: &mut Frame)
+    where
+        T: 'static + Copy,
+    {
+        self.device.update_storage_buffer(data, buf, f);
+    }
+
+    /// Write `data` to a [`StorageBuffer`], see [`Device::try_update_storage_buffer`].
+    pub fn try_update_storage_buffer<T>(
+        &mut self,
+        buf: &StorageBuffer,
+        data: &[T],
+        f: &mut Frame,
+    ) -> Result<(), Error>
+    where
+        T: 'static + Copy,
+    {
+        self.device.try_update_storage_buffer(data, buf, f)
+    }
+
+    pub fn binding_group(&self, layout: &BindingGroupLayout, binds: &[&dyn Bind]) -> BindingGroup {
+        self.device.create_binding_group(layout, binds)
+    }
+
+    pub fn try_binding_group(
+        &self,
+        layout: &BindingGroupLayout,
+        binds: &[&dyn Bind],
+    ) -> Result<BindingGroup, Error> {
+        self.device.try_create_binding_group(layout, binds)
+    }
+
+    pub fn sampler(&self, min_filter: Filter, mag_filter: Filter) -> Sampler {
+        self.device.create_sampler(min_filter, mag_filter)
+    }
+
+    pub fn sampler_with(&self, desc: &SamplerDescriptor) -> Sampler {
+        self.device.create_sampler_with(desc)
+    }
+
+    pub fn pipeline<T>(&self, w: u32, h: u32, blending: Blending) -> T
+    where
+        T: AbstractPipeline<'static>,
+    {
+        self.try_pipeline(w, h, blending)
+            .unwrap_or_else(|err| panic!("fatal: {}", err))
+    }
+
+    /// Create a pipeline from its description.
+    ///
+    /// The description is checked against the shaders' vertex inputs and
+    /// bindings, failing with [`Error::ShaderMismatch`] if they disagree.
+    pub fn try_pipeline<T>(&self, w: u32, h: u32, blending: Blending) -> Result<T, Error>
+    where
+        T: AbstractPipeline<'static>,
+    {
+        self.try_pipeline_msaa(w, h, blending, 1)
+    }
+
+    /// Create a pipeline rendering to multisampled targets, such as the ones
+    /// created with [`Renderer::framebuffer_msaa`]. The sample count must
+    /// match the target's.
+    pub fn pipeline_msaa<T>(&self, w: u32, h: u32, blending: Blending, samples: u32) -> T
+    where
+        T: AbstractPipeline<'static>,
+    {
+        self.try_pipeline_msaa(w, h, blending, samples)
+            .unwrap_or_else(|err| panic!("fatal: {}", err))
+    }
+
+    pub fn try_pipeline_msaa<T>(
+        &self,
+        w: u32,
+        h: u32,
+        blending: Blending,
+        samples: u32,
+    ) -> Result<T, Error>
+    where
+        T: AbstractPipeline<'static>,
+    {
+        let desc = T::description();
+
+        self.try_pipeline_with_shaders(
+            w,
+            h,
+            blending,
+            samples,
+            desc.vertex_shader,
+            desc.fragment_shader,
+        )
+    }
+
+    /// Create a pipeline with the given SPIR-V shaders instead of the ones in
+    /// its description, eg. shaders compiled at runtime with [`compile_glsl`].
+    pub fn try_pipeline_with_shaders<T>(
+        &self,
+        w: u32,
+        h: u32,
+        blending: Blending,
+        samples: u32,
+        vertex_shader: &[u8],
+        fragment_shader: &[u8],
+    ) -> Result<T, Error>
     where
-        T: 'static + Copy,
+        T: AbstractPipeline<'static>,
     {
-        self.device.create_buffer(verts)
+        let desc = T::description();
+        reflect::validate(&desc, vertex_shader, fragment_shader)?;
+
+        let pip_layout = self.device.create_pipeline_layout(desc.pipeline_layout);
+        let vertex_layout =
+            VertexLayout::from(desc.vertex_layout).with_stride(desc.vertex_stride)?;
+        let mut location = desc.vertex_layout.len() as u32;
+        let vertex_slots = desc
+            .vertex_slots
+            .iter()
+            .map(|slot| {
+                let layout = VertexLayout::with_step_mode(slot.formats, slot.step_mode, location);
+                location += slot.formats.len() as u32;
+                layout.with_stride(slot.stride)
+            })
+            .collect::<Result<_, _>>()?;
+        let vs =
+            self.device
+                .try_create_shader("vertex shader", vertex_shader, ShaderStage::Vertex)?;
+        let fs = self.device.try_create_shader(
+            "fragment shader",
+            fragment_shader,
+            ShaderStage::Fragment,
+        )?;
+
+        Ok(T::setup(
+            self.device.create_pipeline(
+                pip_layout,
+                VertexState {
+                    layout: vertex_layout,
+                    slots: vertex_slots,
+                    index_format: desc.index_format,
+                },
+                TargetState {
+                    colors: if desc.targets.is_empty() {
+                        vec![ColorTarget::new(desc.format, blending)]
+                    } else {
+                        desc.targets.to_vec()
+                    },
+                    samples,
+                    depth_stencil: desc.depth_stencil,
+                },
+                desc.primitive,
+                ShaderStages {
+                    vertex: &vs,
+                    fragment: &fs,
+                    entry_points: desc.entry_points,
+                },
+            ),
+            &self.device,
+            w,
+            h,
+        ))
     }
 
-    pub fn uniform_buffer<T>(&self, buf: &[T]) -> UniformBuffer
+    pub fn compute_pipeline<T>(&self) -> T
     where
-        T: 'static + Copy,
+        T: AbstractComputePipeline<'static>,
     {
-        self.device.create_uniform_buffer(buf)
+        self.try_compute_pipeline()
+            .unwrap_or_else(|err| panic!("fatal: {}", err))
     }
 
-    pub fn binding_group(&self, layout: &BindingGroupLayout, binds: &[&dyn Bind]) -> BindingGroup {
-        self.device.create_binding_group(layout, binds)
-    }
-
-    pub fn sampler(&self, min_filter: Filter, mag_filter: Filter) -> Sampler {
-        self.device.create_sampler(min_filter, mag_filter)
+    pub fn try_compute_pipeline<T>(&self) -> Result<T, Error>
+    where
+        T: AbstractComputePipeline<'static>,
+    {
+        self.try_compute_pipeline_with_shader(T::description().compute_shader)
     }
 
-    pub fn pipeline<T>(&self, w: u32, h: u32, blending: Blending) -> T
+    /// Create a compute pipeline with the given SPIR-V shader instead of the
+    /// one in its description. See [`Renderer::try_pipeline_with_shaders`].
+    pub fn try_compute_pipeline_with_shader<T>(&self, compute_shader: &[u8]) -> Result<T, Error>
     where
-        T: AbstractPipeline<'static>,
+        T: AbstractComputePipeline<'static>,
     {
         let desc = T::description();
         let pip_layout = self.device.create_pipeline_layout(desc.pipeline_layout);
-        let vertex_layout = VertexLayout::from(desc.vertex_layout);
-        let vs =
-            self.device
-                .create_shader("vertex shader", desc.vertex_shader, ShaderStage::Vertex);
-        let fs = self.device.create_shader(
-            "fragment shader",
-            desc.fragment_shader,
-            ShaderStage::Fragment,
-        );
+        let cs = self.device.try_create_shader(
+            "compute shader",
+            compute_shader,
+            ShaderStage::Compute,
+        )?;
 
-        T::setup(
+        Ok(T::setup(
             self.device
-                .create_pipeline(pip_layout, vertex_layout, blending, &vs, &fs),
+                .create_compute_pipeline(pip_layout, &cs, desc.entry_point),
             &self.device,
-            w,
-            h,
-        )
+        ))
     }
 
+    /// Read the framebuffer into host memory, and call `f` with its texels.
+    /// This blocks until the data is available, and `f` is called before
+    /// returning.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the readback fails, see [`Renderer::try_read`].
+    #[deprecated(note = "use `Renderer::try_read`, which returns the texels")]
     pub fn read<F>(&mut self, fb: &Framebuffer, f: F)
     where
         F: 'static + FnOnce(&[u8]),
     {
-        let mut encoder = self.device.create_command_encoder();
+        match self.try_read(fb) {
+            Ok(data) => f(data.as_slice()),
+            Err(err) => panic!("fatal: {}", err),
+        }
+    }
+
+    /// Read the framebuffer into host memory, blocking until the data is
+    /// available. To read without blocking, or to read a region of any
+    /// texture, see [`Renderer::read_texture`].
+    pub fn try_read(&mut self, fb: &Framebuffer) -> Result<Vec<u8>, Error> {
+        let rect = Rect::origin(fb.texture.w, fb.texture.h);
+
+        self.read_texture(&fb.texture, rect, Conversion::None)
+            .and_then(|readback| readback.wait(&self.device))
+    }
+
+    /// Read a region of a texture into host memory. The returned [`Readback`]
+    /// can be polled, or waited on until the data is available.
+    pub fn read_texture(
+        &mut self,
+        texture: &Texture,
+        rect: Rect<u32>,
+        conversion: Conversion,
+    ) -> Result<Readback, Error> {
+        if rect.x1 >= rect.x2 || rect.y1 >= rect.y2 || rect.x2 > texture.w || rect.y2 > texture.h {
+            return Err(Error::ReadOutOfBounds(rect));
+        }
+        let (w, h) = (rect.x2 - rect.x1, rect.y2 - rect.y1);
+        let row = texture.format.bytesize() as u32 * w;
+        let row_pitch = Readback::row_pitch(row);
+        let bytesize = (row_pitch * h) as u64;
 
-        let bytesize = 4 * fb.size();
+        let mut encoder = self.device.create_command_encoder();
         let dst = self.device.device.create_buffer(&wgpu::BufferDescriptor {
-            size: bytesize as u64,
+            size: bytesize,
             usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
         });
 
         encoder.copy_texture_to_buffer(
             wgpu::TextureCopyView {
-                texture: &fb.texture.wgpu,
+                texture: &texture.wgpu,
                 mip_level: 0,
                 array_layer: 0,
                 origin: wgpu::Origin3d {
-                    x: 0.0,
-                    y: 0.0,
+                    x: rect.x1 as f32,
+                    y: rect.y1 as f32,
                     z: 0.0,
                 },
             },
             wgpu::BufferCopyView {
                 buffer: &dst,
                 offset: 0,
-                // TODO: Must be a multiple of 256
-                row_pitch: 4 * fb.texture.w,
-                image_height: fb.texture.h,
+                row_pitch,
+                image_height: h,
+            },
+            wgpu::Extent3d {
+                width: w,
+                height: h,
+                depth: 1,
             },
-            fb.texture.extent,
         );
         self.device.submit(&[encoder.finish()]);
 
-        let mut buffer: Vec<u8> = Vec::with_capacity(bytesize);
+        let conversion = if texture.format.is_bgra() {
+            conversion
+        } else {
+            Conversion::None
+        };
+        Ok(Readback::new(dst, bytesize, row, row_pitch, conversion))
+    }
 
-        dst.map_read_async(
-            0,
-            bytesize as u64,
-            move |result: wgpu::BufferMapAsyncResult<&[u8]>| match result {
-                Ok(ref mapping) => {
-                    buffer.extend_from_slice(mapping.data);
-                    if buffer.len() == bytesize {
-                        f(unsafe { std::mem::transmute(buffer.as_slice()) });
-                    }
+    // MUTABLE API ////////////////////////////////////////////////////////////
+
+    /// Generate the mip chain of a texture on the GPU, by downsampling each
+    /// level into the next with a linear filter, for every array layer.
+    /// Textures created without a mip chain are left untouched.
+    pub fn generate_mipmaps(&mut self, texture: &Texture) {
+        if texture.mip_levels <= 1 {
+            return;
+        }
+        let device = &self.device;
+        let mipmapper = self.mipmapper.get_or_insert_with(|| Mipmapper::new(device));
+        let index = mipmapper.prepare(device, texture.format);
+        let pipeline = &mipmapper.pipelines[index].1;
+
+        // A level of the texture can't be rendered to while the level above it
+        // is sampled from, so each level is rendered into a scratch target and
+        // then copied into place. The scratch resources have to outlive the
+        // submission.
+        let mut scratch = Vec::new();
+        let mut encoder = device.create_command_encoder();
+
+        for layer in 0..texture.layers {
+            for level in 1..texture.mip_levels {
+                let (w, h) = ((texture.w >> level).max(1), (texture.h >> level).max(1));
+                let src = texture.level_view(level - 1, layer);
+                let dst = device.create_framebuffer_with_format(w, h, texture.format);
+                let binding = BindingGroup::new(
+                    0,
+                    device.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        layout: &pipeline.layout.sets[0].wgpu,
+                        bindings: &[
+                            wgpu::Binding {
+                                binding: 0,
+                                resource: wgpu::BindingResource::TextureView(&src),
+                            },
+                            mipmapper.sampler.binding(1),
+                        ],
+                    }),
+                );
+                {
+                    // The scratch target is uninitialized, so don't load it.
+                    let mut pass = Pass::begin(
+                        &mut encoder,
+                        &dst.texture.view,
+                        PassOp::Clear(Rgba::TRANSPARENT),
+                    );
+
+                    pass.set_pipeline(pipeline);
+                    pass.set_binding(&binding, &[]);
+                    pass.draw_buffer(&mipmapper.quad);
                 }
-                Err(ref err) => panic!("{:?}", err),
-            },
-        );
+                encoder.copy_texture_to_texture(
+                    wgpu::TextureCopyView {
+                        texture: &dst.texture.wgpu,
+                        mip_level: 0,
+                        array_layer: 0,
+                        origin: wgpu::Origin3d {
+                            x: 0.0,
+                            y: 0.0,
+                            z: 0.0,
+                        },
+                    },
+                    wgpu::TextureCopyView {
+                        texture: &texture.wgpu,
+                        mip_level: level,
+                        array_layer: layer,
+                        origin: wgpu::Origin3d {
+                            x: 0.0,
+                            y: 0.0,
+                            z: 0.0,
+                        },
+                    },
+                    wgpu::Extent3d {
+                        width: w,
+                        height: h,
+                        depth: 1,
+                    },
+                );
+                scratch.push((src, dst, binding));
+            }
+        }
+        self.device.submit(&[encoder.finish()]);
     }
 
-    // MUTABLE API ////////////////////////////////////////////////////////////
+    /// Rebuild `pipeline` from its shader files if they changed since they
+    /// were last compiled, with the given size. Returns whether the pipeline
+    /// was rebuilt.
+    ///
+    /// On error, eg. if a shader fails to compile, the existing pipeline is
+    /// kept, and the files are only compiled again once they change.
+    /// Requires the `glsl` feature.
+    #[cfg(feature = "glsl")]
+    pub fn reload_pipeline<T>(
+        &self,
+        pipeline: &mut T,
+        files: &mut ShaderFiles,
+        w: u32,
+        h: u32,
+        blending: Blending,
+        samples: u32,
+    ) -> Result<bool, Error>
+    where
+        T: AbstractPipeline<'static>,
+    {
+        if !files.changed() {
+            return Ok(false);
+        }
+        let (vs, fs) = files.compile()?;
+
+        *pipeline = self.try_pipeline_with_shaders(w, h, blending, samples, &vs, &fs)?;
+
+        Ok(true)
+    }
+
+    /// Rebuild a compute pipeline from its shader file if it changed since it
+    /// was last compiled. Returns whether the pipeline was rebuilt. Errors are
+    /// handled as in [`Renderer::reload_pipeline`].
+    /// Requires the `glsl` feature.
+    #[cfg(feature = "glsl")]
+    pub fn reload_compute_pipeline<T>(
+        &self,
+        pipeline: &mut T,
+        file: &mut ComputeShaderFile,
+    ) -> Result<bool, Error>
+    where
+        T: AbstractComputePipeline<'static>,
+    {
+        if !file.changed() {
+            return Ok(false);
+        }
+        let cs = file.compile()?;
+
+        *pipeline = self.try_compute_pipeline_with_shader(&cs)?;
+
+        Ok(true)
+    }
 
     pub fn update_pipeline<'a, T>(&mut self, pip: &'a T, p: T::PrepareContext, f: &mut Frame)
     where
@@ -1425,7 +4001,42 @@ impl Renderer {
     {
         if let Some((buf, unifs)) = pip.prepare(p) {
             self.device
-                .update_uniform_buffer::<T::Uniforms>(unifs.as_slice(), buf, &mut f.encoder);
+                .update_uniform_buffer::<T::Uniforms>(unifs.as_slice(), buf, f);
+        }
+    }
+
+    /// Write `entries` to a [`DynamicBuffer`] as part of the given frame.
+    pub fn update_dynamic_buffer<T>(&mut self, buf: &DynamicBuffer<T>, entries: &[T], f: &mut Frame)
+    where
+        T: 'static + Copy,
+    {
+        self.device.update_dynamic_buffer(entries, buf, f);
+    }
+
+    /// Write `entries` to a [`DynamicBuffer`], see [`Device::try_update_dynamic_buffer`].
+    pub fn try_update_dynamic_buffer<T>(
+        &mut self,
+        buf: &DynamicBuffer<T>,
+        entries: &[T],
+        f: &mut Frame,
+    ) -> Result<(), Error>
+    where
+        T: 'static + Copy,
+    {
+        self.device.try_update_dynamic_buffer(entries, buf, f)
+    }
+
+    pub fn update_compute_pipeline<'a, T>(
+        &mut self,
+        pip: &'a T,
+        p: T::PrepareContext,
+        f: &mut Frame,
+    ) where
+        T: AbstractComputePipeline<'a>,
+    {
+        if let Some((buf, unifs)) = pip.prepare(p) {
+            self.device
+                .update_uniform_buffer::<T::Uniforms>(unifs.as_slice(), buf, f);
         }
     }
 
@@ -1435,15 +4046,24 @@ impl Renderer {
     }
 
     pub fn submit(&mut self, frame: Frame) {
-        self.device.submit(&[frame.encoder.finish()]);
+        self.device.submit_frame(frame);
     }
 
     pub fn prepare(&mut self, commands: &[Op]) {
-        let mut encoder = self.device.create_command_encoder();
+        self.try_prepare(commands)
+            .unwrap_or_else(|err| panic!("fatal: {}", err))
+    }
+
+    /// Encode and submit the given operations. If any of them fails, nothing
+    /// is submitted and the error is returned.
+    pub fn try_prepare(&mut self, commands: &[Op]) -> Result<(), Error> {
+        let mut frame = self.frame();
         for c in commands.iter() {
-            c.encode(&mut self.device, &mut encoder);
+            c.encode(&mut self.device, &mut frame)?;
         }
-        self.device.submit(&[encoder.finish()]);
+        self.submit(frame);
+
+        Ok(())
     }
 }
 
@@ -1455,21 +4075,311 @@ pub enum Op<'a> {
 }
 
 impl<'a> Op<'a> {
-    fn encode(&self, dev: &mut Device, encoder: &mut wgpu::CommandEncoder) {
+    fn encode(&self, dev: &mut Device, frame: &mut Frame) -> Result<(), Error> {
         match *self {
-            Op::Clear(f, color) => {
-                f.clear(color, dev, encoder);
-            }
-            Op::Fill(f, buf) => {
-                f.fill(buf, dev, encoder);
-            }
-            Op::Transfer(f, buf, w, h, tw, th) => {
-                f.transfer(buf, w, h, tw, th, dev, encoder);
-            }
-            Op::Blit(f, src, dst) => {
-                f.blit(src, dst, encoder);
+            Op::Clear(f, color) => f.clear(color, dev, frame),
+            Op::Fill(f, buf) => f.fill(buf, dev, frame),
+            Op::Transfer(f, buf, w, h, tw, th) => f.transfer(buf, w, h, tw, th, dev, frame),
+            Op::Blit(f, src, dst) => f.blit(src, dst, frame),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// DeviceOptions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Which kind of graphics adapter to prefer when creating a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PowerPreference {
+    /// Let the backend decide.
+    Default,
+    /// Prefer an integrated GPU.
+    #[default]
+    LowPower,
+    /// Prefer a discrete GPU.
+    HighPerformance,
+}
+
+impl PowerPreference {
+    fn to_wgpu(self) -> wgpu::PowerPreference {
+        match self {
+            Self::Default => wgpu::PowerPreference::Default,
+            Self::LowPower => wgpu::PowerPreference::LowPower,
+            Self::HighPerformance => wgpu::PowerPreference::HighPerformance,
+        }
+    }
+}
+
+/// Options used to select an adapter and create a [`Device`]. The options
+/// a device was created with are available through [`Device::options`].
+///
+/// wgpu 0.3 doesn't report which adapter was chosen: its `Adapter` has no
+/// name, vendor or device type to query. The power preference is the only
+/// information there is about the adapter, so it is what to log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceOptions {
+    pub power_preference: PowerPreference,
+    pub anisotropic_filtering: bool,
+    pub max_bind_groups: u32,
+    /// The size of the chunks uploads are staged in, in bytes. Larger
+    /// uploads get a chunk of their own.
+    pub staging_chunk_size: u64,
+}
+
+impl DeviceOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn power_preference(mut self, power_preference: PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    pub fn anisotropic_filtering(mut self, enabled: bool) -> Self {
+        self.anisotropic_filtering = enabled;
+        self
+    }
+
+    pub fn max_bind_groups(mut self, n: u32) -> Self {
+        self.max_bind_groups = n;
+        self
+    }
+
+    pub fn staging_chunk_size(mut self, size: u64) -> Self {
+        self.staging_chunk_size = size;
+        self
+    }
+}
+
+impl Default for DeviceOptions {
+    fn default() -> Self {
+        Self {
+            power_preference: PowerPreference::default(),
+            anisotropic_filtering: false,
+            max_bind_groups: wgpu::Limits::default().max_bind_groups,
+            staging_chunk_size: 1 << 20,
+        }
+    }
+}
+
+impl fmt::Display for DeviceOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "power preference: {:?}, anisotropic filtering: {}, max bind groups: {}, \
+             staging chunk size: {}",
+            self.power_preference,
+            self.anisotropic_filtering,
+            self.max_bind_groups,
+            self.staging_chunk_size
+        )
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Staging
+///////////////////////////////////////////////////////////////////////////////
+
+/// Copy `data` to `dst`, with consecutive elements `stride` bytes apart.
+///
+/// The copy is typed, so that any padding within or between elements is
+/// never read as bytes.
+///
+/// # Safety
+///
+/// `dst` must be valid for writes of `data.len() * stride` bytes, and
+/// `stride` must be at least the size of `T`.
+unsafe fn copy_strided<T: Copy>(data: &[T], dst: *mut u8, stride: usize) {
+    debug_assert!(stride >= std::mem::size_of::<T>());
+
+    for (i, elem) in data.iter().enumerate() {
+        std::ptr::write_unaligned(dst.add(i * stride) as *mut T, *elem);
+    }
+}
+
+/// The memory of a staging chunk, while it is mapped for writing.
+struct Mapping {
+    data: *mut u8,
+    len: usize,
+}
+
+// The mapping is only accessed through its chunk, and is dropped before the
+// chunk is unmapped.
+unsafe impl Send for Mapping {}
+
+/// A host-visible buffer that uploads are sub-allocated from.
+struct Chunk {
+    buffer: wgpu::Buffer,
+    size: wgpu::BufferAddress,
+    offset: wgpu::BufferAddress,
+    mapping: Arc<Mutex<Option<Mapping>>>,
+}
+
+impl Chunk {
+    fn new(device: &wgpu::Device, size: wgpu::BufferAddress) -> Self {
+        let chunk = Self {
+            buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                size,
+                usage: wgpu::BufferUsage::MAP_WRITE | wgpu::BufferUsage::COPY_SRC,
+            }),
+            size,
+            offset: 0,
+            mapping: Arc::new(Mutex::new(None)),
+        };
+        chunk.map();
+
+        // A new buffer isn't in use by any submission, so it is mapped by
+        // the next poll, without waiting on the GPU.
+        device.poll(false);
+        debug_assert!(chunk.is_mapped());
+
+        chunk
+    }
+
+    /// Request the chunk to be mapped, once the GPU is done with it.
+    fn map(&self) {
+        let shared = self.mapping.clone();
+
+        self.buffer.map_write_async(
+            0,
+            self.size,
+            move |result: wgpu::BufferMapAsyncResult<&mut [u8]>| {
+                if let Ok(mapping) = result {
+                    *shared.lock().unwrap() = Some(Mapping {
+                        data: mapping.data.as_mut_ptr(),
+                        len: mapping.data.len(),
+                    });
+                }
+            },
+        );
+    }
+
+    fn is_mapped(&self) -> bool {
+        self.mapping.lock().unwrap().is_some()
+    }
+
+    /// Write `data` at the given offset, with elements `stride` bytes apart.
+    fn write<T: Copy>(&mut self, offset: wgpu::BufferAddress, data: &[T], stride: usize) {
+        let mapping = self.mapping.lock().unwrap();
+        let mapping = mapping
+            .as_ref()
+            .unwrap_or_else(|| panic!("fatal: staging chunk is not mapped"));
+        let offset = offset as usize;
+
+        assert!(stride >= std::mem::size_of::<T>());
+        assert!(offset + data.len() * stride <= mapping.len);
+
+        // This is safe, since the range was checked to lie within the
+        // mapping, which is valid until the chunk is unmapped.
+        unsafe {
+            copy_strided(data, mapping.data.add(offset), stride);
+        }
+    }
+
+    fn unmap(&mut self) {
+        self.mapping.lock().unwrap().take();
+        self.buffer.unmap();
+    }
+}
+
+/// Reusable staging memory for uploads to the GPU, owned by the [`Device`].
+///
+/// Uploads are sub-allocated from mapped chunks, which are then owned by the
+/// [`Frame`] whose commands copy from them. When the frame is submitted, its
+/// chunks are unmapped, and mapped again once the GPU is done with them,
+/// after which they are reused. Chunks larger than the configured chunk size,
+/// which are allocated for oversized uploads, are released instead.
+struct StagingBelt {
+    chunk_size: wgpu::BufferAddress,
+    /// Chunks in use by submitted commands, waiting to be mapped.
+    closed: Vec<Chunk>,
+    /// Mapped chunks, ready to be written to.
+    free: Vec<Chunk>,
+}
+
+impl StagingBelt {
+    /// The alignment of uploads within a chunk. See [`BUFFER_ALIGNMENT`].
+    const ALIGNMENT: wgpu::BufferAddress = BUFFER_ALIGNMENT;
+
+    fn new(chunk_size: wgpu::BufferAddress) -> Self {
+        Self {
+            chunk_size,
+            closed: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    /// Stage `data` in one of the given chunks, taking a chunk from the belt
+    /// if none of them has room. Returns the buffer and offset to copy the
+    /// data from.
+    fn upload<'a, T: Copy>(
+        &mut self,
+        device: &wgpu::Device,
+        data: &[T],
+        stride: usize,
+        chunks: &'a mut Vec<Chunk>,
+    ) -> (&'a wgpu::Buffer, wgpu::BufferAddress) {
+        let align = Self::ALIGNMENT;
+        let size = (data.len() * stride) as wgpu::BufferAddress;
+        let size = size.div_ceil(align) * align;
+
+        let index = match chunks.iter().position(|c| c.offset + size <= c.size) {
+            Some(i) => i,
+            None => {
+                self.recall(device);
+
+                let chunk = match self.free.iter().position(|c| c.size >= size) {
+                    Some(i) => self.free.swap_remove(i),
+                    None => Chunk::new(device, self.chunk_size.max(size)),
+                };
+                chunks.push(chunk);
+                chunks.len() - 1
             }
+        };
+        let chunk = &mut chunks[index];
+        let offset = chunk.offset;
+
+        chunk.write(offset, data, stride);
+        chunk.offset += size;
+
+        (&chunk.buffer, offset)
+    }
+
+    /// Submit commands which copy from the given chunks. The chunks are
+    /// unmapped before `submit` is called, and requested to be mapped again
+    /// after.
+    fn submit(&mut self, mut chunks: Vec<Chunk>, submit: impl FnOnce()) {
+        for chunk in chunks.iter_mut() {
+            chunk.unmap();
         }
+        submit();
+
+        for chunk in chunks.drain(..) {
+            chunk.map();
+            self.closed.push(chunk);
+        }
+    }
+
+    /// Move chunks which were mapped again to the free list, and release the
+    /// ones allocated for oversized uploads.
+    fn recall(&mut self, device: &wgpu::Device) {
+        device.poll(false);
+
+        let (mapped, closed): (Vec<_>, Vec<_>) = self.closed.drain(..).partition(Chunk::is_mapped);
+        let chunk_size = self.chunk_size;
+
+        self.closed = closed;
+        self.free.extend(
+            mapped
+                .into_iter()
+                .filter(|c| c.size <= chunk_size)
+                .map(|mut c| {
+                    c.offset = 0;
+                    c
+                }),
+        );
     }
 }
 
@@ -1479,36 +4389,83 @@ impl<'a> Op<'a> {
 
 pub struct Device {
     device: wgpu::Device,
-    surface: wgpu::Surface,
+    instance: wgpu::Instance,
+    surface: Option<Surface>,
+    options: DeviceOptions,
+    staging: Mutex<StagingBelt>,
 }
 
 impl Device {
     pub fn new(window: RawWindowHandle) -> Self {
+        Self::with_options(Some(window), DeviceOptions::default())
+    }
+
+    /// Create a device without a surface, for off-screen rendering.
+    pub fn headless() -> Self {
+        Self::with_options(None, DeviceOptions::default())
+    }
+
+    /// Create a device with the given options. If no window is given, the
+    /// device is headless.
+    pub fn with_options(window: Option<RawWindowHandle>, options: DeviceOptions) -> Self {
         let instance = wgpu::Instance::new();
-        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::LowPower,
+        let surface = window.map(|w| Surface {
+            wgpu: Arc::new(instance.create_surface(w)),
         });
-        let surface = instance.create_surface(window);
 
         Self {
-            device: adapter.request_device(&wgpu::DeviceDescriptor {
-                extensions: wgpu::Extensions {
-                    anisotropic_filtering: false,
-                },
-                limits: wgpu::Limits::default(),
-            }),
+            device: Self::request_device(&instance, &options),
+            staging: Mutex::new(StagingBelt::new(options.staging_chunk_size)),
+            instance,
             surface,
+            options,
         }
     }
 
+    /// The options this device was created with. Since wgpu 0.3 has no
+    /// adapter info, these stand in for it when logging, see [`DeviceOptions`].
+    pub fn options(&self) -> &DeviceOptions {
+        &self.options
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_none()
+    }
+
     pub fn create_command_encoder(&self) -> wgpu::CommandEncoder {
         self.device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor { todo: 0 })
     }
 
-    pub fn create_swap_chain(&self, w: u32, h: u32, mode: PresentMode) -> wgpu::SwapChain {
+    /// Create a surface for the given window. Swap chains for this surface
+    /// can then be created with [`Device::create_surface_swap_chain`].
+    pub fn create_surface(&self, window: RawWindowHandle) -> Surface {
+        Surface {
+            wgpu: Arc::new(self.instance.create_surface(window)),
+        }
+    }
+
+    /// Create a swap chain for the surface the device was created with.
+    pub fn create_swap_chain(
+        &self,
+        w: u32,
+        h: u32,
+        mode: PresentMode,
+    ) -> Result<wgpu::SwapChain, Error> {
+        let surface = self.surface.as_ref().ok_or(Error::NoSurface)?;
+
+        Ok(self.create_surface_swap_chain(surface, w, h, mode))
+    }
+
+    pub fn create_surface_swap_chain(
+        &self,
+        surface: &Surface,
+        w: u32,
+        h: u32,
+        mode: PresentMode,
+    ) -> wgpu::SwapChain {
         let desc = SwapChain::descriptor(w, h, mode);
-        self.device.create_swap_chain(&self.surface, &desc)
+        self.device.create_swap_chain(&surface.wgpu, &desc)
     }
 
     pub fn create_pipeline_layout(&self, ss: &[Set]) -> PipelineLayout {
@@ -1519,13 +4476,23 @@ impl Device {
         PipelineLayout { sets }
     }
 
-    pub fn create_shader(&self, _name: &str, source: &[u8], _stage: ShaderStage) -> Shader {
+    pub fn create_shader(&self, name: &str, source: &[u8], stage: ShaderStage) -> Shader {
+        self.try_create_shader(name, source, stage)
+            .unwrap_or_else(|err| panic!("fatal: {}: {}", name, err))
+    }
+
+    pub fn try_create_shader(
+        &self,
+        _name: &str,
+        source: &[u8],
+        _stage: ShaderStage,
+    ) -> Result<Shader, Error> {
         let buf = std::io::Cursor::new(source);
-        let spv = wgpu::read_spirv(buf).unwrap();
+        let spv = wgpu::read_spirv(buf).map_err(|err| Error::InvalidSpirv(err.to_string()))?;
 
-        Shader {
+        Ok(Shader {
             module: self.device.create_shader_module(spv.as_slice()),
-        }
+        })
     }
 
     pub fn create_encoder(&self) -> wgpu::CommandEncoder {
@@ -1534,6 +4501,52 @@ impl Device {
     }
 
     pub fn create_texture(&self, w: u32, h: u32) -> Texture {
+        self.create_texture_with_format(w, h, TextureFormat::Rgba8Unorm)
+    }
+
+    pub fn create_texture_with_format(&self, w: u32, h: u32, format: TextureFormat) -> Texture {
+        self.create_texture_with(w, h, format, 1, 1, wgpu::TextureUsage::empty())
+    }
+
+    /// Create a texture with a full mip chain. Only the base level is written
+    /// to by [`Canvas`] operations; use [`Renderer::generate_mipmaps`] to fill
+    /// in the rest.
+    pub fn create_mipmapped_texture(&self, w: u32, h: u32, format: TextureFormat) -> Texture {
+        let mip_levels = Texture::full_mip_levels(w, h);
+
+        self.create_texture_with(w, h, format, mip_levels, 1, wgpu::TextureUsage::empty())
+    }
+
+    /// Create a texture with `layers` layers of the same size, to be bound
+    /// as a [`BindingType::SampledTextureArray`]. Filling it with a [`Canvas`]
+    /// operation writes all layers, one after the other.
+    pub fn create_texture_array(
+        &self,
+        w: u32,
+        h: u32,
+        layers: u32,
+        format: TextureFormat,
+    ) -> Texture {
+        self.create_texture_with(w, h, format, 1, layers, wgpu::TextureUsage::empty())
+    }
+
+    /// Create a texture which compute shaders can write to, when bound as a
+    /// [`BindingType::StorageTexture`]. Not all formats can be used for storage;
+    /// `Rgba8Unorm` and the float formats are widely supported.
+    pub fn create_storage_texture(&self, w: u32, h: u32, format: TextureFormat) -> Texture {
+        self.create_texture_with(w, h, format, 1, 1, wgpu::TextureUsage::STORAGE)
+    }
+
+    /// Create a sampled texture, with `usage` in addition to the default usage.
+    fn create_texture_with(
+        &self,
+        w: u32,
+        h: u32,
+        format: TextureFormat,
+        mip_levels: u32,
+        layers: u32,
+        usage: wgpu::TextureUsage,
+    ) -> Texture {
         let texture_extent = wgpu::Extent3d {
             width: w,
             height: h,
@@ -1541,25 +4554,80 @@ impl Device {
         };
         let texture = self.device.create_texture(&wgpu::TextureDescriptor {
             size: texture_extent,
-            array_layer_count: 1,
-            mip_level_count: 1,
+            array_layer_count: layers,
+            mip_level_count: mip_levels,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
-            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+            format: format.to_wgpu(),
+            usage: wgpu::TextureUsage::SAMPLED
+                | wgpu::TextureUsage::COPY_DST
+                | wgpu::TextureUsage::COPY_SRC
+                | usage,
         });
-        let texture_view = texture.create_default_view();
+        let texture_view = if layers > 1 {
+            texture.create_view(&wgpu::TextureViewDescriptor {
+                format: format.to_wgpu(),
+                dimension: wgpu::TextureViewDimension::D2Array,
+                aspect: wgpu::TextureAspect::All,
+                base_mip_level: 0,
+                level_count: mip_levels,
+                base_array_layer: 0,
+                array_layer_count: layers,
+            })
+        } else {
+            texture.create_default_view()
+        };
 
         Texture {
             wgpu: texture,
             view: texture_view,
             extent: texture_extent,
+            format,
+            mip_levels,
+            layers,
             w,
             h,
         }
     }
 
     pub fn create_framebuffer(&self, w: u32, h: u32) -> Framebuffer {
+        self.create_framebuffer_with_format(w, h, TextureFormat::Bgra8Unorm)
+    }
+
+    pub fn create_framebuffer_with_format(
+        &self,
+        w: u32,
+        h: u32,
+        format: TextureFormat,
+    ) -> Framebuffer {
+        self.create_framebuffer_with(w, h, format, wgpu::TextureUsage::empty())
+    }
+
+    /// Create a framebuffer which compute shaders can also write to, when its
+    /// texture is bound as a [`BindingType::StorageTexture`].
+    pub fn create_storage_framebuffer(&self, w: u32, h: u32, format: TextureFormat) -> Framebuffer {
+        self.create_framebuffer_with(w, h, format, wgpu::TextureUsage::STORAGE)
+    }
+
+    /// Create a multisampled framebuffer. Passes render to a multisampled
+    /// texture, which is resolved into the framebuffer's texture.
+    pub fn create_framebuffer_msaa(&self, w: u32, h: u32, samples: u32) -> Framebuffer {
+        let format = TextureFormat::Bgra8Unorm;
+
+        Framebuffer {
+            msaa: self.create_multisample(w, h, format, samples),
+            ..self.create_framebuffer_with_format(w, h, format)
+        }
+    }
+
+    /// Create a framebuffer, with `usage` in addition to the default usage.
+    fn create_framebuffer_with(
+        &self,
+        w: u32,
+        h: u32,
+        format: TextureFormat,
+        usage: wgpu::TextureUsage,
+    ) -> Framebuffer {
         let extent = wgpu::Extent3d {
             width: w,
             height: h,
@@ -1571,11 +4639,12 @@ impl Device {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Bgra8Unorm,
+            format: format.to_wgpu(),
             usage: wgpu::TextureUsage::SAMPLED
                 | wgpu::TextureUsage::COPY_DST
                 | wgpu::TextureUsage::COPY_SRC
-                | wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+                | wgpu::TextureUsage::OUTPUT_ATTACHMENT
+                | usage,
         });
         let view = texture.create_default_view();
 
@@ -1584,10 +4653,80 @@ impl Device {
                 wgpu: texture,
                 view,
                 extent,
+                format,
+                mip_levels: 1,
+                layers: 1,
                 w,
                 h,
             },
+            msaa: None,
+            usage,
+        }
+    }
+
+    pub fn create_depth_buffer(
+        &self,
+        w: u32,
+        h: u32,
+        format: DepthFormat,
+        samples: u32,
+    ) -> DepthBuffer {
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: w,
+                height: h,
+                depth: 1,
+            },
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: samples,
+            dimension: wgpu::TextureDimension::D2,
+            format: format.to_wgpu(),
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+        });
+        let view = texture.create_default_view();
+
+        DepthBuffer {
+            wgpu: texture,
+            view,
+            format,
+            samples,
+            w,
+            h,
+        }
+    }
+
+    /// Create a multisampled render target, or `None` if `samples` is `1`.
+    fn create_multisample(
+        &self,
+        w: u32,
+        h: u32,
+        format: TextureFormat,
+        samples: u32,
+    ) -> Option<Multisample> {
+        if samples <= 1 {
+            return None;
         }
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: w,
+                height: h,
+                depth: 1,
+            },
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: samples,
+            dimension: wgpu::TextureDimension::D2,
+            format: format.to_wgpu(),
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::SAMPLED,
+        });
+        let view = texture.create_default_view();
+
+        Some(Multisample {
+            texture,
+            view,
+            samples,
+        })
     }
 
     pub fn create_binding_group(
@@ -1595,11 +4734,21 @@ impl Device {
         layout: &BindingGroupLayout,
         binds: &[&dyn Bind],
     ) -> BindingGroup {
-        assert_eq!(
-            binds.len(),
-            layout.size,
-            "layout slot count does not match bindings"
-        );
+        self.try_create_binding_group(layout, binds)
+            .unwrap_or_else(|err| panic!("fatal: {}", err))
+    }
+
+    pub fn try_create_binding_group(
+        &self,
+        layout: &BindingGroupLayout,
+        binds: &[&dyn Bind],
+    ) -> Result<BindingGroup, Error> {
+        if binds.len() != layout.size {
+            return Err(Error::BindingCountMismatch {
+                expected: layout.size,
+                actual: binds.len(),
+            });
+        }
 
         let mut bindings = Vec::new();
 
@@ -1607,26 +4756,49 @@ impl Device {
             bindings.push(b.binding(i as u32));
         }
 
-        BindingGroup::new(
+        Ok(BindingGroup::new(
             layout.set_index,
             self.device.create_bind_group(&wgpu::BindGroupDescriptor {
                 layout: &layout.wgpu,
                 bindings: bindings.as_slice(),
             }),
-        )
+        ))
     }
 
-    pub fn create_buffer<T>(&self, vertices: &[T]) -> VertexBuffer
-    where
-        T: 'static + Copy,
-    {
-        VertexBuffer {
-            wgpu: self
-                .device
-                .create_buffer_mapped(vertices.len(), wgpu::BufferUsage::VERTEX)
-                .fill_from_slice(vertices),
-            size: vertices.len() as u32,
+    /// Create a vertex buffer holding `vertices`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the formats of `V` don't add up to its size.
+    pub fn create_buffer<V: Vertex>(&self, vertices: &[V]) -> VertexBuffer<V> {
+        self.try_create_buffer(vertices)
+            .unwrap_or_else(|err| panic!("fatal: {}", err))
+    }
+
+    /// Create a vertex buffer. Fails if the vertex formats of `V` add up to
+    /// more than its size.
+    pub fn try_create_buffer<V: Vertex>(&self, vertices: &[V]) -> Result<VertexBuffer<V>, Error> {
+        let size = V::FORMATS.iter().map(|f| f.bytesize()).sum::<usize>();
+
+        if size > std::mem::size_of::<V>() {
+            return Err(Error::VertexFormatMismatch(format!(
+                "vertex formats {:?} take up {} bytes, more than the {} bytes of the vertex type",
+                V::FORMATS,
+                size,
+                std::mem::size_of::<V>()
+            )));
         }
+
+        Ok(VertexBuffer {
+            wgpu: self.create_buffer_with_capacity(
+                vertices,
+                vertices.len(),
+                wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+            ),
+            capacity: std::mem::size_of_val(vertices) as wgpu::BufferAddress,
+            size: vertices.len() as u32,
+            vertex: PhantomData,
+        })
     }
 
     pub fn create_uniform_buffer<T>(&self, buf: &[T]) -> UniformBuffer
@@ -1648,27 +4820,65 @@ impl Device {
         }
     }
 
+    /// Create a [`DynamicBuffer`] with one entry per element of `entries`.
+    /// The buffer's capacity is fixed to the number of entries.
+    pub fn create_dynamic_buffer<T>(&self, entries: &[T]) -> DynamicBuffer<T>
+    where
+        T: 'static + Copy,
+    {
+        let stride = DynamicBuffer::<T>::stride();
+        let mapped = self.device.create_buffer_mapped::<u8>(
+            entries.len() * stride,
+            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        );
+
+        // This is safe, since the mapping holds exactly one stride per entry.
+        unsafe {
+            copy_strided(entries, mapped.data.as_mut_ptr(), stride);
+        }
+
+        DynamicBuffer {
+            capacity: entries.len(),
+            wgpu: mapped.finish(),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn create_storage_buffer<T>(&self, data: &[T]) -> StorageBuffer
+    where
+        T: 'static + Copy,
+    {
+        StorageBuffer {
+            size: std::mem::size_of_val(data),
+            wgpu: self
+                .device
+                .create_buffer_mapped::<T>(
+                    data.len(),
+                    wgpu::BufferUsage::STORAGE
+                        | wgpu::BufferUsage::COPY_DST
+                        | wgpu::BufferUsage::COPY_SRC,
+                )
+                .fill_from_slice(data),
+        }
+    }
+
     pub fn create_index(&self, indices: &[u16]) -> IndexBuffer {
-        let index_buf = self
-            .device
-            .create_buffer_mapped(indices.len(), wgpu::BufferUsage::INDEX)
-            .fill_from_slice(indices);
-        IndexBuffer { wgpu: index_buf }
+        self.create_index_buffer(indices, indices.len())
+    }
+
+    /// Create an index buffer with 32-bit indices, for meshes with more
+    /// vertices than 16-bit indices can address.
+    pub fn create_index_u32(&self, indices: &[u32]) -> IndexBuffer {
+        self.create_index_buffer(indices, indices.len())
     }
 
     pub fn create_sampler(&self, min_filter: Filter, mag_filter: Filter) -> Sampler {
+        self.create_sampler_with(&SamplerDescriptor::new(min_filter, mag_filter))
+    }
+
+    pub fn create_sampler_with(&self, desc: &SamplerDescriptor) -> Sampler {
         Sampler {
-            wgpu: self.device.create_sampler(&wgpu::SamplerDescriptor {
-                address_mode_u: wgpu::AddressMode::Repeat,
-                address_mode_v: wgpu::AddressMode::Repeat,
-                address_mode_w: wgpu::AddressMode::Repeat,
-                mag_filter: mag_filter.to_wgpu(),
-                min_filter: min_filter.to_wgpu(),
-                mipmap_filter: wgpu::FilterMode::Nearest,
-                lod_min_clamp: -100.0,
-                lod_max_clamp: 100.0,
-                compare_function: wgpu::CompareFunction::Always,
-            }),
+            wgpu: self.device.create_sampler(&desc.to_wgpu()),
         }
     }
 
@@ -1694,104 +4904,524 @@ impl Device {
         &self,
         slice: &[T],
         buf: &UniformBuffer,
-        encoder: &mut wgpu::CommandEncoder,
+        frame: &mut Frame,
     ) {
-        let src = self
-            .device
-            .create_buffer_mapped::<T>(
-                slice.len(),
-                wgpu::BufferUsage::UNIFORM
-                    | wgpu::BufferUsage::COPY_SRC
-                    | wgpu::BufferUsage::MAP_WRITE,
-            )
-            .fill_from_slice(slice);
+        self.write_buffer(slice, &buf.wgpu, 0, frame);
+    }
 
-        encoder.copy_buffer_to_buffer(
-            &src,
-            0,
+    /// Write `entries` to the first entries of a [`DynamicBuffer`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more entries than the buffer holds, see
+    /// [`Device::try_update_dynamic_buffer`].
+    pub fn update_dynamic_buffer<T: Copy + 'static>(
+        &self,
+        entries: &[T],
+        buf: &DynamicBuffer<T>,
+        frame: &mut Frame,
+    ) {
+        self.try_update_dynamic_buffer(entries, buf, frame)
+            .unwrap_or_else(|err| panic!("fatal: {}", err))
+    }
+
+    /// Write `entries` to the first entries of a [`DynamicBuffer`]. Fails
+    /// without writing anything if there are more entries than the buffer
+    /// holds.
+    pub fn try_update_dynamic_buffer<T: Copy + 'static>(
+        &self,
+        entries: &[T],
+        buf: &DynamicBuffer<T>,
+        frame: &mut Frame,
+    ) -> Result<(), Error> {
+        Self::check_range(0, entries.len(), buf.capacity)?;
+
+        let stride = DynamicBuffer::<T>::stride();
+        let (src, src_offset) = self.stage(entries, stride, &mut frame.staged);
+
+        frame.encoder.copy_buffer_to_buffer(
+            src,
+            src_offset,
             &buf.wgpu,
             0,
-            (std::mem::size_of::<T>() * slice.len()) as wgpu::BufferAddress,
+            (entries.len() * stride) as wgpu::BufferAddress,
         );
+        Ok(())
+    }
+
+    /// Write `data` to the start of a [`StorageBuffer`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the data is larger than the buffer, see
+    /// [`Device::try_update_storage_buffer`].
+    pub fn update_storage_buffer<T: Copy + 'static>(
+        &self,
+        data: &[T],
+        buf: &StorageBuffer,
+        frame: &mut Frame,
+    ) {
+        self.try_update_storage_buffer(data, buf, frame)
+            .unwrap_or_else(|err| panic!("fatal: {}", err))
+    }
+
+    /// Write `data` to the start of a [`StorageBuffer`]. Fails without
+    /// writing anything if the data is larger than the buffer.
+    pub fn try_update_storage_buffer<T: Copy + 'static>(
+        &self,
+        data: &[T],
+        buf: &StorageBuffer,
+        frame: &mut Frame,
+    ) -> Result<(), Error> {
+        Self::check_range(0, std::mem::size_of_val(data), buf.size)?;
+        self.write_buffer(data, &buf.wgpu, 0, frame);
+
+        Ok(())
+    }
+
+    /// Replace the vertices of a buffer. If they don't fit, the buffer is
+    /// reallocated with at least twice its capacity.
+    pub fn update_vertex_buffer<V: Vertex>(
+        &self,
+        vertices: &[V],
+        buf: &mut VertexBuffer<V>,
+        frame: &mut Frame,
+    ) {
+        let size = std::mem::size_of_val(vertices) as wgpu::BufferAddress;
+
+        if size > buf.capacity {
+            let capacity = Self::grow(buf.capacity, vertices);
+
+            buf.wgpu = self.create_buffer_with_capacity(
+                vertices,
+                capacity,
+                wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+            );
+            buf.capacity = (capacity * std::mem::size_of::<V>()) as wgpu::BufferAddress;
+        } else {
+            self.write_buffer(vertices, &buf.wgpu, 0, frame);
+        }
+        buf.size = vertices.len() as u32;
+    }
+
+    /// Overwrite the vertices of a buffer, starting at vertex `offset`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range lies outside of the buffer's vertices, see
+    /// [`Device::try_update_vertex_buffer_range`].
+    pub fn update_vertex_buffer_range<V: Vertex>(
+        &self,
+        offset: u32,
+        vertices: &[V],
+        buf: &VertexBuffer<V>,
+        frame: &mut Frame,
+    ) {
+        self.try_update_vertex_buffer_range(offset, vertices, buf, frame)
+            .unwrap_or_else(|err| panic!("fatal: {}", err))
+    }
+
+    /// Overwrite the vertices of a buffer, starting at vertex `offset`. Fails
+    /// without writing anything if the range lies outside of the buffer's
+    /// vertices.
+    pub fn try_update_vertex_buffer_range<V: Vertex>(
+        &self,
+        offset: u32,
+        vertices: &[V],
+        buf: &VertexBuffer<V>,
+        frame: &mut Frame,
+    ) -> Result<(), Error> {
+        Self::check_range(offset as usize, vertices.len(), buf.size as usize)?;
+
+        let offset = (offset as usize * std::mem::size_of::<V>()) as wgpu::BufferAddress;
+        self.write_buffer(vertices, &buf.wgpu, offset, frame);
+
+        Ok(())
+    }
+
+    /// Replace the indices of a buffer. If they don't fit, or are of a
+    /// different format, the buffer is reallocated.
+    pub fn update_index_buffer<I: IndexType>(
+        &self,
+        indices: &[I],
+        buf: &mut IndexBuffer,
+        frame: &mut Frame,
+    ) {
+        let size = std::mem::size_of_val(indices) as wgpu::BufferAddress;
+
+        if size > buf.capacity || buf.format != I::FORMAT {
+            *buf = self.create_index_buffer(indices, Self::grow(buf.capacity, indices));
+        } else {
+            self.write_buffer(indices, &buf.wgpu, 0, frame);
+            buf.size = indices.len() as u32;
+        }
+    }
+
+    /// Overwrite the indices of a buffer, starting at index `offset`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range lies outside of the buffer's indices, or if the
+    /// indices are of a different format than the buffer, see
+    /// [`Device::try_update_index_buffer_range`].
+    pub fn update_index_buffer_range<I: IndexType>(
+        &self,
+        offset: u32,
+        indices: &[I],
+        buf: &IndexBuffer,
+        frame: &mut Frame,
+    ) {
+        self.try_update_index_buffer_range(offset, indices, buf, frame)
+            .unwrap_or_else(|err| panic!("fatal: {}", err))
+    }
+
+    /// Overwrite the indices of a buffer, starting at index `offset`. Fails
+    /// without writing anything if the range lies outside of the buffer's
+    /// indices, or if the indices are of a different format than the buffer.
+    pub fn try_update_index_buffer_range<I: IndexType>(
+        &self,
+        offset: u32,
+        indices: &[I],
+        buf: &IndexBuffer,
+        frame: &mut Frame,
+    ) -> Result<(), Error> {
+        if buf.format != I::FORMAT {
+            return Err(Error::IndexFormatMismatch {
+                expected: buf.format,
+                actual: I::FORMAT,
+            });
+        }
+        Self::check_range(offset as usize, indices.len(), buf.size as usize)?;
+
+        let offset = (offset as usize * std::mem::size_of::<I>()) as wgpu::BufferAddress;
+        self.write_buffer(indices, &buf.wgpu, offset, frame);
+
+        Ok(())
+    }
+
+    /// Check that `len` elements starting at `offset` fit in a buffer of
+    /// `size` elements.
+    fn check_range(offset: usize, len: usize, size: usize) -> Result<(), Error> {
+        let range = offset..offset + len;
+
+        if range.end > size {
+            return Err(Error::WriteOutOfBounds { range, size });
+        }
+        Ok(())
     }
 
     // MUTABLE API ////////////////////////////////////////////////////////////
 
+    /// Submit the given commands.
     pub fn submit(&mut self, cmds: &[wgpu::CommandBuffer]) {
         self.device.get_queue().submit(cmds);
     }
 
+    /// Submit the commands recorded into a frame. The staging chunks its
+    /// uploads were written to are reused once the commands have executed.
+    pub fn submit_frame(&mut self, frame: Frame) {
+        let Frame { encoder, staged } = frame;
+        let device = &mut self.device;
+
+        self.staging.get_mut().unwrap().submit(staged, || {
+            device.get_queue().submit(&[encoder.finish()]);
+        });
+    }
+
     // PRIVATE API ////////////////////////////////////////////////////////////
 
+    /// Create a buffer with room for `capacity` elements, the first of
+    /// which are initialized with `data`.
+    fn create_buffer_with_capacity<T: Copy + 'static>(
+        &self,
+        data: &[T],
+        capacity: usize,
+        usage: wgpu::BufferUsage,
+    ) -> wgpu::Buffer {
+        let mapped = self.device.create_buffer_mapped::<T>(capacity, usage);
+        mapped.data[..data.len()].copy_from_slice(data);
+        mapped.finish()
+    }
+
+    fn create_index_buffer<I: IndexType>(&self, indices: &[I], capacity: usize) -> IndexBuffer {
+        IndexBuffer {
+            wgpu: self.create_buffer_with_capacity(
+                indices,
+                capacity,
+                wgpu::BufferUsage::INDEX | wgpu::BufferUsage::COPY_DST,
+            ),
+            capacity: (capacity * std::mem::size_of::<I>()) as wgpu::BufferAddress,
+            size: indices.len() as u32,
+            format: I::FORMAT,
+        }
+    }
+
+    /// The number of elements a buffer of `capacity` bytes grows to, to fit
+    /// `data`.
+    fn grow<T>(capacity: wgpu::BufferAddress, data: &[T]) -> usize {
+        let doubled = 2 * capacity as usize / std::mem::size_of::<T>().max(1);
+        doubled.max(data.len())
+    }
+
+    /// Write `data` to a buffer at the given offset, through the staging belt.
+    fn write_buffer<T: Copy>(
+        &self,
+        data: &[T],
+        buf: &wgpu::Buffer,
+        offset: wgpu::BufferAddress,
+        frame: &mut Frame,
+    ) {
+        let size = std::mem::size_of_val(data);
+
+        if size == 0 {
+            return;
+        }
+        let (src, src_offset) = self.stage(data, std::mem::size_of::<T>(), &mut frame.staged);
+
+        frame.encoder.copy_buffer_to_buffer(
+            src,
+            src_offset,
+            buf,
+            offset,
+            size as wgpu::BufferAddress,
+        );
+    }
+
+    /// Stage `data` in one of the given chunks, with consecutive elements
+    /// `stride` bytes apart. Returns the buffer and offset to copy it from.
+    fn stage<'a, T: Copy>(
+        &self,
+        data: &[T],
+        stride: usize,
+        chunks: &'a mut Vec<Chunk>,
+    ) -> (&'a wgpu::Buffer, wgpu::BufferAddress) {
+        self.staging
+            .lock()
+            .unwrap()
+            .upload(&self.device, data, stride, chunks)
+    }
+
+    fn request_device(instance: &wgpu::Instance, options: &DeviceOptions) -> wgpu::Device {
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: options.power_preference.to_wgpu(),
+        });
+
+        adapter.request_device(&wgpu::DeviceDescriptor {
+            extensions: wgpu::Extensions {
+                anisotropic_filtering: options.anisotropic_filtering,
+            },
+            limits: wgpu::Limits {
+                max_bind_groups: options.max_bind_groups,
+            },
+        })
+    }
+
     fn create_pipeline(
         &self,
         pipeline_layout: PipelineLayout,
-        vertex_layout: VertexLayout,
-        blending: Blending,
-        vs: &Shader,
-        fs: &Shader,
+        vertex: VertexState,
+        targets: TargetState,
+        primitive: PrimitiveState,
+        shaders: ShaderStages,
     ) -> Pipeline {
-        let vertex_attrs = vertex_layout.to_wgpu();
+        let vertex_buffers = std::iter::once(&vertex.layout)
+            .chain(vertex.slots.iter())
+            .map(VertexLayout::to_wgpu)
+            .collect::<Vec<_>>();
 
-        let mut sets = Vec::new();
-        for s in pipeline_layout.sets.iter() {
-            sets.push(&s.wgpu);
-        }
-        let layout = &self
-            .device
-            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                bind_group_layouts: sets.as_slice(),
-            });
+        let layout = &self.create_wgpu_pipeline_layout(&pipeline_layout);
 
-        let (src_factor, dst_factor, operation) = blending.to_wgpu();
+        let color_states = targets
+            .colors
+            .iter()
+            .map(ColorTarget::to_wgpu)
+            .collect::<Vec<_>>();
 
         let wgpu = self
             .device
             .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
                 layout,
                 vertex_stage: wgpu::ProgrammableStageDescriptor {
-                    module: &vs.module,
-                    entry_point: "main",
+                    module: &shaders.vertex.module,
+                    entry_point: shaders.entry_points.vertex,
                 },
                 fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
-                    module: &fs.module,
-                    entry_point: "main",
+                    module: &shaders.fragment.module,
+                    entry_point: shaders.entry_points.fragment,
                 }),
-                rasterization_state: Some(wgpu::RasterizationStateDescriptor {
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: wgpu::CullMode::None,
-                    depth_bias: 0,
-                    depth_bias_slope_scale: 0.0,
-                    depth_bias_clamp: 0.0,
-                }),
-                primitive_topology: wgpu::PrimitiveTopology::TriangleList,
-                color_states: &[wgpu::ColorStateDescriptor {
-                    // TODO: Try Bgra8UnormSrgb
-                    format: wgpu::TextureFormat::Bgra8Unorm,
-                    color_blend: wgpu::BlendDescriptor {
-                        src_factor,
-                        dst_factor,
-                        operation,
-                    },
-                    alpha_blend: wgpu::BlendDescriptor {
-                        src_factor,
-                        dst_factor,
-                        operation,
-                    },
-                    write_mask: wgpu::ColorWrite::ALL,
-                }],
-                depth_stencil_state: None,
-                index_format: wgpu::IndexFormat::Uint16,
-                vertex_buffers: &[vertex_attrs],
-                sample_count: 1,
+                rasterization_state: Some(primitive.to_wgpu()),
+                primitive_topology: primitive.topology.to_wgpu(),
+                color_states: color_states.as_slice(),
+                depth_stencil_state: targets.depth_stencil.map(DepthStencilState::to_wgpu),
+                index_format: vertex.index_format.to_wgpu(),
+                vertex_buffers: vertex_buffers.as_slice(),
+                sample_count: targets.samples,
                 sample_mask: !0,
                 alpha_to_coverage_enabled: false,
             });
 
+        let inputs = std::iter::once(&vertex.layout)
+            .chain(vertex.slots.iter())
+            .map(|l| VertexInput {
+                formats: Cow::Owned(l.formats.clone()),
+                stride: l.size,
+            })
+            .collect::<Vec<_>>()
+            .into();
+
         Pipeline {
             layout: pipeline_layout,
-            vertex_layout,
+            vertex_layout: vertex.layout,
+            vertex_slots: vertex.slots,
+            inputs,
+            wgpu,
+        }
+    }
+
+    fn create_compute_pipeline(
+        &self,
+        pipeline_layout: PipelineLayout,
+        cs: &Shader,
+        entry_point: &str,
+    ) -> ComputePipeline {
+        let layout = &self.create_wgpu_pipeline_layout(&pipeline_layout);
+        let wgpu = self
+            .device
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                layout,
+                compute_stage: wgpu::ProgrammableStageDescriptor {
+                    module: &cs.module,
+                    entry_point,
+                },
+            });
+
+        ComputePipeline {
+            layout: pipeline_layout,
             wgpu,
         }
     }
+
+    fn create_wgpu_pipeline_layout(
+        &self,
+        pipeline_layout: &PipelineLayout,
+    ) -> wgpu::PipelineLayout {
+        let mut sets = Vec::new();
+        for s in pipeline_layout.sets.iter() {
+            sets.push(&s.wgpu);
+        }
+        self.device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                bind_group_layouts: sets.as_slice(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_float_bits() {
+        assert_eq!(f16_bits(0.0), 0x0000);
+        assert_eq!(f16_bits(-0.0), 0x8000);
+        assert_eq!(f16_bits(1.0), 0x3c00);
+        assert_eq!(f16_bits(-2.0), 0xc000);
+        assert_eq!(f16_bits(0.5), 0x3800);
+        assert_eq!(f16_bits(65504.0), 0x7bff);
+        // Out of range values become infinite, subnormals are flushed to zero.
+        assert_eq!(f16_bits(65536.0), 0x7c00);
+        assert_eq!(f16_bits(-1e10), 0xfc00);
+        assert_eq!(f16_bits(1e-6), 0x0000);
+        assert_eq!(f16_bits(f32::INFINITY), 0x7c00);
+        assert_eq!(f16_bits(f32::NEG_INFINITY), 0xfc00);
+        assert_eq!(f16_bits(f32::NAN) & 0x7e00, 0x7e00);
+    }
+
+    #[test]
+    fn readback_row_pitch() {
+        assert_eq!(Readback::row_pitch(1), 256);
+        assert_eq!(Readback::row_pitch(256), 256);
+        assert_eq!(Readback::row_pitch(257), 512);
+        assert_eq!(Readback::row_pitch(100 * 4), 512);
+    }
+
+    #[test]
+    fn readback_unpad() {
+        let (row, row_pitch) = (8, 256);
+        let mut data = vec![0xffu8; row_pitch * 2];
+
+        data[..row].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        data[row_pitch..row_pitch + row].copy_from_slice(&[9, 10, 11, 12, 13, 14, 15, 16]);
+
+        assert_eq!(
+            Readback::unpad(&data, row as u32, row_pitch as u32, Conversion::None),
+            (1..=16).collect::<Vec<u8>>()
+        );
+        assert_eq!(
+            Readback::unpad(&data, row as u32, row_pitch as u32, Conversion::BgraToRgba),
+            vec![3, 2, 1, 4, 7, 6, 5, 8, 11, 10, 9, 12, 15, 14, 13, 16]
+        );
+    }
+
+    #[test]
+    fn dynamic_buffer_stride() {
+        assert_eq!(DynamicBuffer::<()>::stride(), 256);
+        assert_eq!(DynamicBuffer::<u8>::stride(), 256);
+        assert_eq!(DynamicBuffer::<[f32; 16]>::stride(), 256);
+        assert_eq!(DynamicBuffer::<[u8; 256]>::stride(), 256);
+        assert_eq!(DynamicBuffer::<[u8; 257]>::stride(), 512);
+        assert_eq!(DynamicBuffer::<[f32; 100]>::stride(), 512);
+    }
+
+    #[test]
+    fn vertex_layout_stride() {
+        /// A vertex with padding after its last field.
+        #[repr(C, align(16))]
+        #[derive(Copy, Clone)]
+        struct Padded(f32, f32, f32);
+
+        impl Vertex for Padded {
+            const FORMATS: &'static [VertexFormat] = &[VertexFormat::Float3];
+        }
+
+        let packed = VertexLayout::from(Padded::FORMATS);
+        assert_eq!(packed.size, 12);
+
+        let padded = VertexLayout::from(Padded::FORMATS)
+            .with_stride(Some(std::mem::size_of::<Padded>()))
+            .unwrap();
+        assert_eq!(padded.size, 16);
+        assert_eq!(
+            VertexInput::of::<Padded>(),
+            VertexInput {
+                formats: Cow::Owned(padded.formats.clone()),
+                stride: padded.size,
+            }
+        );
+        assert_ne!(
+            VertexInput::of::<Padded>(),
+            VertexInput {
+                formats: Cow::Owned(packed.formats.clone()),
+                stride: packed.size,
+            }
+        );
+
+        assert!(matches!(
+            VertexLayout::from(Padded::FORMATS).with_stride(Some(8)),
+            Err(Error::VertexFormatMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn write_range() {
+        assert_eq!(Device::check_range(0, 4, 4), Ok(()));
+        assert_eq!(Device::check_range(4, 0, 4), Ok(()));
+        assert_eq!(
+            Device::check_range(2, 3, 4),
+            Err(Error::WriteOutOfBounds {
+                range: 2..5,
+                size: 4
+            })
+        );
+    }
 }
@@ -0,0 +1,910 @@
+#![deny(clippy::all, clippy::use_self)]
+
+//! A minimal SPIR-V reflection, used to check shaders against the
+//! [`PipelineDescription`] they are created with.
+//!
+//! Only the parts of a module relevant to pipeline creation are reflected:
+//! the inputs of the vertex stage, and the resources bound by each stage.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::{BindingType, Error, PipelineDescription, ShaderStage, VertexFormat};
+
+const MAGIC: u32 = 0x0723_0203;
+const HEADER_LEN: usize = 5;
+
+// Opcodes.
+const OP_ENTRY_POINT: u32 = 15;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+
+// Decorations.
+const DECORATION_BLOCK: u32 = 2;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_NON_WRITABLE: u32 = 24;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+
+// Storage classes.
+const STORAGE_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_INPUT: u32 = 1;
+const STORAGE_UNIFORM: u32 = 2;
+const STORAGE_STORAGE_BUFFER: u32 = 12;
+
+// Execution models.
+const EXECUTION_MODEL_VERTEX: u32 = 0;
+const EXECUTION_MODEL_FRAGMENT: u32 = 4;
+const EXECUTION_MODEL_GL_COMPUTE: u32 = 5;
+
+const DIM_2D: u32 = 1;
+
+///////////////////////////////////////////////////////////////////////////
+// Validation
+///////////////////////////////////////////////////////////////////////////
+
+/// Check that the entry points, vertex inputs and bindings of the given
+/// shaders match the pipeline description.
+pub fn validate(desc: &PipelineDescription, vs: &[u8], fs: &[u8]) -> Result<(), Error> {
+    let vertex = Module::parse(vs)?;
+    let fragment = Module::parse(fs)?;
+
+    validate_entry_point(&vertex, desc.entry_points.vertex, ShaderStage::Vertex)?;
+    validate_entry_point(&fragment, desc.entry_points.fragment, ShaderStage::Fragment)?;
+    validate_inputs(desc, &vertex)?;
+    validate_bindings(desc, &vertex, ShaderStage::Vertex, &fragment)?;
+    validate_bindings(desc, &fragment, ShaderStage::Fragment, &vertex)?;
+
+    Ok(())
+}
+
+fn validate_entry_point(
+    module: &Module,
+    entry_point: &str,
+    stage: ShaderStage,
+) -> Result<(), Error> {
+    let model = match stage {
+        ShaderStage::Vertex => EXECUTION_MODEL_VERTEX,
+        ShaderStage::Fragment => EXECUTION_MODEL_FRAGMENT,
+        ShaderStage::Compute => EXECUTION_MODEL_GL_COMPUTE,
+    };
+    if module.entry_point(model, entry_point).is_some() {
+        Ok(())
+    } else {
+        Err(mismatch(format!(
+            "{} shader has no entry point named {:?}",
+            name(&stage),
+            entry_point
+        )))
+    }
+}
+
+/// Check the inputs of the vertex entry point against the vertex layout and
+/// slots. Inputs of other entry points in the module are ignored.
+fn validate_inputs(desc: &PipelineDescription, module: &Module) -> Result<(), Error> {
+    let interface = module
+        .entry_point(EXECUTION_MODEL_VERTEX, desc.entry_points.vertex)
+        .map_or(&[][..], |e| e.interface.as_slice());
+    let formats: Vec<VertexFormat> = desc
+        .vertex_layout
+        .iter()
+        .chain(desc.vertex_slots.iter().flat_map(|s| s.formats.iter()))
+        .cloned()
+        .collect();
+
+    for input in module
+        .inputs
+        .iter()
+        .filter(|i| interface.contains(&i.variable))
+    {
+        let format = formats.get(input.location as usize).ok_or_else(|| {
+            mismatch(format!(
+                "vertex shader input at location {} has no matching vertex attribute",
+                input.location
+            ))
+        })?;
+        let (scalar, components) = match format {
+            VertexFormat::Float => (Scalar::Float, 1),
+            VertexFormat::Float2 => (Scalar::Float, 2),
+            VertexFormat::Float3 => (Scalar::Float, 3),
+            VertexFormat::Float4 => (Scalar::Float, 4),
+            VertexFormat::UByte4 => (Scalar::Float, 4),
+        };
+        if input.scalar != scalar || input.components != components {
+            return Err(mismatch(format!(
+                "vertex shader input at location {} is {}, but the vertex attribute is {:?}",
+                input.location, input, format
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Check the resources bound by `module` against the pipeline layout. The
+/// module of the other stage is used to report bindings used by both.
+fn validate_bindings(
+    desc: &PipelineDescription,
+    module: &Module,
+    stage: ShaderStage,
+    other: &Module,
+) -> Result<(), Error> {
+    for d in module.descriptors.iter() {
+        let binding = desc
+            .pipeline_layout
+            .get(d.set as usize)
+            .and_then(|set| set.0.get(d.binding as usize))
+            .ok_or_else(|| {
+                mismatch(format!(
+                    "{} shader {} at set {}, binding {} is missing from the pipeline layout",
+                    name(&stage),
+                    d.resource,
+                    d.set,
+                    d.binding
+                ))
+            })?;
+
+        if !d.resource.matches(&binding.binding) {
+            return Err(mismatch(format!(
+                "{} shader {} at set {}, binding {} does not match the layout's {:?}",
+                name(&stage),
+                d.resource,
+                d.set,
+                d.binding,
+                binding.binding
+            )));
+        }
+        if binding.stage != stage {
+            if other
+                .descriptors
+                .iter()
+                .any(|o| o.set == d.set && o.binding == d.binding)
+            {
+                return Err(mismatch(format!(
+                    "set {}, binding {} is used by both the vertex and fragment shaders, \
+                     but a binding is only visible to one stage: use a separate binding \
+                     in each shader",
+                    d.set, d.binding
+                )));
+            }
+            return Err(mismatch(format!(
+                "set {}, binding {} is used by the {} shader, but is only visible to the {:?} stage",
+                d.set,
+                d.binding,
+                name(&stage),
+                binding.stage
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn name(stage: &ShaderStage) -> &'static str {
+    match stage {
+        ShaderStage::Vertex => "vertex",
+        ShaderStage::Fragment => "fragment",
+        ShaderStage::Compute => "compute",
+    }
+}
+
+fn mismatch(msg: String) -> Error {
+    Error::ShaderMismatch(msg)
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Reflection
+///////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Scalar {
+    Float,
+    Int,
+    Uint,
+}
+
+/// A vertex shader input, with matrices and arrays split up by location.
+#[derive(Debug)]
+struct Input {
+    /// The id of the variable the input belongs to.
+    variable: u32,
+    location: u32,
+    scalar: Scalar,
+    components: u32,
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scalar = match self.scalar {
+            Scalar::Float => "float",
+            Scalar::Int => "int",
+            Scalar::Uint => "uint",
+        };
+        if self.components == 1 {
+            write!(f, "a {}", scalar)
+        } else {
+            write!(f, "a {}-component {} vector", self.components, scalar)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Resource {
+    UniformBuffer,
+    StorageBuffer {
+        readonly: bool,
+    },
+    Sampler,
+    SampledTexture {
+        multisampled: bool,
+        arrayed: bool,
+    },
+    StorageTexture,
+    /// Any other resource, eg. a combined image sampler, or a texture which
+    /// isn't two-dimensional. These can't be described by a [`BindingType`].
+    Unsupported,
+}
+
+impl Resource {
+    fn matches(&self, ty: &BindingType) -> bool {
+        match (self, ty) {
+            (Self::UniformBuffer, BindingType::UniformBuffer)
+            | (Self::UniformBuffer, BindingType::UniformBufferDynamic)
+            | (Self::StorageBuffer { .. }, BindingType::StorageBuffer)
+            | (Self::StorageBuffer { readonly: true }, BindingType::ReadonlyStorageBuffer)
+            | (Self::Sampler, BindingType::Sampler)
+            | (Self::StorageTexture, BindingType::StorageTexture) => true,
+            (
+                Self::SampledTexture {
+                    multisampled,
+                    arrayed,
+                },
+                _,
+            ) => match ty {
+                BindingType::SampledTexture => !multisampled && !arrayed,
+                BindingType::SampledTextureMultisampled => *multisampled && !arrayed,
+                BindingType::SampledTextureArray => !multisampled && *arrayed,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UniformBuffer => write!(f, "uniform buffer"),
+            Self::StorageBuffer { readonly: true } => write!(f, "read-only storage buffer"),
+            Self::StorageBuffer { readonly: false } => write!(f, "storage buffer"),
+            Self::Sampler => write!(f, "sampler"),
+            Self::SampledTexture {
+                multisampled,
+                arrayed,
+            } => write!(
+                f,
+                "{}{}sampled texture",
+                if *multisampled { "multisampled " } else { "" },
+                if *arrayed { "array " } else { "" }
+            ),
+            Self::StorageTexture => write!(f, "storage texture"),
+            Self::Unsupported => write!(f, "unsupported resource"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Descriptor {
+    set: u32,
+    binding: u32,
+    resource: Resource,
+}
+
+#[derive(Debug)]
+enum Type {
+    Scalar(Scalar),
+    Vector(u32, u32),
+    Matrix(u32, u32),
+    Image {
+        dim: u32,
+        arrayed: bool,
+        multisampled: bool,
+        sampled: u32,
+    },
+    Sampler,
+    SampledImage,
+    Array(u32, u32),
+    RuntimeArray,
+    Struct(usize),
+    Pointer(u32),
+    Other,
+}
+
+#[derive(Debug, Default)]
+struct Decorations {
+    location: Option<u32>,
+    set: Option<u32>,
+    binding: Option<u32>,
+    builtin: bool,
+    block: bool,
+    buffer_block: bool,
+    non_writable: bool,
+    non_writable_members: usize,
+}
+
+#[derive(Debug)]
+struct EntryPoint {
+    model: u32,
+    name: String,
+    /// The ids of the input and output variables used by the entry point.
+    interface: Vec<u32>,
+}
+
+/// The reflected parts of a SPIR-V module.
+#[derive(Debug)]
+struct Module {
+    entry_points: Vec<EntryPoint>,
+    inputs: Vec<Input>,
+    descriptors: Vec<Descriptor>,
+}
+
+impl Module {
+    fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let invalid = |msg: &str| Error::InvalidSpirv(msg.to_owned());
+
+        let chunks = bytes.chunks_exact(4);
+
+        if !chunks.remainder().is_empty() {
+            return Err(invalid("module size must be a multiple of 4"));
+        }
+        let words: Vec<u32> = chunks
+            .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+            .collect();
+
+        if words.len() < HEADER_LEN {
+            return Err(invalid("module is too short"));
+        }
+        if words[0] != MAGIC {
+            return Err(invalid("wrong magic number"));
+        }
+
+        let mut types: HashMap<u32, Type> = HashMap::new();
+        let mut constants: HashMap<u32, u32> = HashMap::new();
+        let mut decorations: HashMap<u32, Decorations> = HashMap::new();
+        // Variables, as `(id, type, storage class)`.
+        let mut variables: Vec<(u32, u32, u32)> = Vec::new();
+        let mut entry_points = Vec::new();
+
+        let mut i = HEADER_LEN;
+        while i < words.len() {
+            let count = (words[i] >> 16) as usize;
+            let opcode = words[i] & 0xffff;
+
+            if count == 0 || i + count > words.len() {
+                return Err(invalid("truncated instruction"));
+            }
+            let ops = &words[i + 1..i + count];
+            let op = |n: usize| {
+                ops.get(n)
+                    .cloned()
+                    .ok_or_else(|| invalid("missing operand"))
+            };
+
+            match opcode {
+                OP_ENTRY_POINT => {
+                    let (name, len) = Self::string(ops.get(2..).unwrap_or(&[]));
+
+                    entry_points.push(EntryPoint {
+                        model: op(0)?,
+                        name,
+                        interface: ops.get(2 + len..).unwrap_or(&[]).to_vec(),
+                    });
+                }
+                OP_TYPE_BOOL => {
+                    types.insert(op(0)?, Type::Other);
+                }
+                OP_TYPE_INT => {
+                    let scalar = if op(2)? == 0 {
+                        Scalar::Uint
+                    } else {
+                        Scalar::Int
+                    };
+                    types.insert(op(0)?, Type::Scalar(scalar));
+                }
+                OP_TYPE_FLOAT => {
+                    types.insert(op(0)?, Type::Scalar(Scalar::Float));
+                }
+                OP_TYPE_VECTOR => {
+                    types.insert(op(0)?, Type::Vector(op(1)?, op(2)?));
+                }
+                OP_TYPE_MATRIX => {
+                    types.insert(op(0)?, Type::Matrix(op(1)?, op(2)?));
+                }
+                OP_TYPE_IMAGE => {
+                    types.insert(
+                        op(0)?,
+                        Type::Image {
+                            dim: op(2)?,
+                            arrayed: op(4)? != 0,
+                            multisampled: op(5)? != 0,
+                            sampled: op(6)?,
+                        },
+                    );
+                }
+                OP_TYPE_SAMPLER => {
+                    types.insert(op(0)?, Type::Sampler);
+                }
+                OP_TYPE_SAMPLED_IMAGE => {
+                    types.insert(op(0)?, Type::SampledImage);
+                }
+                OP_TYPE_ARRAY => {
+                    types.insert(op(0)?, Type::Array(op(1)?, op(2)?));
+                }
+                OP_TYPE_RUNTIME_ARRAY => {
+                    types.insert(op(0)?, Type::RuntimeArray);
+                }
+                OP_TYPE_STRUCT => {
+                    types.insert(op(0)?, Type::Struct(ops.len() - 1));
+                }
+                OP_TYPE_POINTER => {
+                    types.insert(op(0)?, Type::Pointer(op(2)?));
+                }
+                OP_CONSTANT => {
+                    constants.insert(op(1)?, op(2)?);
+                }
+                OP_VARIABLE => {
+                    variables.push((op(1)?, op(0)?, op(2)?));
+                }
+                OP_DECORATE => {
+                    let d = decorations.entry(op(0)?).or_default();
+
+                    match op(1)? {
+                        DECORATION_BLOCK => d.block = true,
+                        DECORATION_BUFFER_BLOCK => d.buffer_block = true,
+                        DECORATION_BUILT_IN => d.builtin = true,
+                        DECORATION_NON_WRITABLE => d.non_writable = true,
+                        DECORATION_LOCATION => d.location = Some(op(2)?),
+                        DECORATION_BINDING => d.binding = Some(op(2)?),
+                        DECORATION_DESCRIPTOR_SET => d.set = Some(op(2)?),
+                        _ => {}
+                    }
+                }
+                OP_MEMBER_DECORATE if op(2)? == DECORATION_NON_WRITABLE => {
+                    decorations.entry(op(0)?).or_default().non_writable_members += 1;
+                }
+                _ => {}
+            }
+            i += count;
+        }
+
+        let none = Decorations::default();
+        let decorations_of = |id: u32| decorations.get(&id).unwrap_or(&none);
+        let mut inputs = Vec::new();
+        let mut descriptors = Vec::new();
+
+        for (id, ty, storage) in variables {
+            let var = decorations_of(id);
+            let pointee = match types.get(&ty) {
+                Some(Type::Pointer(pointee)) => *pointee,
+                _ => return Err(invalid("variable type is not a pointer")),
+            };
+
+            match storage {
+                STORAGE_INPUT if !var.builtin => {
+                    // Built-in blocks such as `gl_PerVertex` are decorated on
+                    // their struct, rather than on the variable.
+                    if decorations_of(pointee).builtin {
+                        continue;
+                    }
+                    let location = var
+                        .location
+                        .ok_or_else(|| invalid("input variable has no location"))?;
+                    Self::inputs(id, pointee, location, &types, &constants, &mut inputs)?;
+                }
+                STORAGE_UNIFORM_CONSTANT | STORAGE_UNIFORM | STORAGE_STORAGE_BUFFER => {
+                    let (set, binding) = match (var.set, var.binding) {
+                        (Some(set), Some(binding)) => (set, binding),
+                        _ => continue,
+                    };
+                    let block = decorations_of(pointee);
+                    let readonly = var.non_writable
+                        || match types.get(&pointee) {
+                            Some(Type::Struct(members)) => {
+                                *members > 0 && block.non_writable_members >= *members
+                            }
+                            _ => false,
+                        };
+                    let resource = match (storage, types.get(&pointee)) {
+                        (STORAGE_UNIFORM, Some(Type::Struct(_))) if block.buffer_block => {
+                            Resource::StorageBuffer { readonly }
+                        }
+                        (STORAGE_UNIFORM, Some(Type::Struct(_))) => Resource::UniformBuffer,
+                        (STORAGE_STORAGE_BUFFER, Some(Type::Struct(_))) => {
+                            Resource::StorageBuffer { readonly }
+                        }
+                        (_, Some(Type::Sampler)) => Resource::Sampler,
+                        (
+                            _,
+                            Some(Type::Image {
+                                dim,
+                                arrayed,
+                                multisampled,
+                                sampled,
+                            }),
+                        ) if *dim == DIM_2D => match sampled {
+                            2 => Resource::StorageTexture,
+                            _ => Resource::SampledTexture {
+                                multisampled: *multisampled,
+                                arrayed: *arrayed,
+                            },
+                        },
+                        _ => Resource::Unsupported,
+                    };
+                    descriptors.push(Descriptor {
+                        set,
+                        binding,
+                        resource,
+                    });
+                }
+                _ => {}
+            }
+        }
+        inputs.sort_by_key(|i| i.location);
+        descriptors.sort_by_key(|d| (d.set, d.binding));
+
+        Ok(Self {
+            entry_points,
+            inputs,
+            descriptors,
+        })
+    }
+
+    /// The entry point with the given execution model and name, if any.
+    fn entry_point(&self, model: u32, name: &str) -> Option<&EntryPoint> {
+        self.entry_points
+            .iter()
+            .find(|e| e.model == model && e.name == name)
+    }
+
+    /// Decode a nul-terminated literal string. Returns the string, and the
+    /// number of words it takes up.
+    fn string(words: &[u32]) -> (String, usize) {
+        let len = words
+            .iter()
+            .position(|w| w.to_le_bytes().contains(&0))
+            .map_or(words.len(), |i| i + 1);
+        let bytes = words[..len]
+            .iter()
+            .flat_map(|w| w.to_le_bytes().to_vec())
+            .take_while(|b| *b != 0)
+            .collect::<Vec<_>>();
+
+        (String::from_utf8_lossy(&bytes).into_owned(), len)
+    }
+
+    /// Collect the inputs of `variable`, of type `ty`, starting at `location`,
+    /// splitting up matrices and arrays, which take up one location per column
+    /// or element. Returns the number of locations used.
+    fn inputs(
+        variable: u32,
+        ty: u32,
+        location: u32,
+        types: &HashMap<u32, Type>,
+        constants: &HashMap<u32, u32>,
+        inputs: &mut Vec<Input>,
+    ) -> Result<u32, Error> {
+        let unsupported = || Error::InvalidSpirv(format!("unsupported input type {}", ty));
+
+        match types.get(&ty) {
+            Some(Type::Scalar(scalar)) => {
+                inputs.push(Input {
+                    variable,
+                    location,
+                    scalar: *scalar,
+                    components: 1,
+                });
+                Ok(1)
+            }
+            Some(Type::Vector(component, components)) => match types.get(component) {
+                Some(Type::Scalar(scalar)) => {
+                    inputs.push(Input {
+                        variable,
+                        location,
+                        scalar: *scalar,
+                        components: *components,
+                    });
+                    Ok(1)
+                }
+                _ => Err(unsupported()),
+            },
+            Some(Type::Matrix(column, columns)) => {
+                for c in 0..*columns {
+                    Self::inputs(variable, *column, location + c, types, constants, inputs)?;
+                }
+                Ok(*columns)
+            }
+            Some(Type::Array(element, length)) => {
+                let length = constants.get(length).ok_or_else(unsupported)?;
+                let mut used = 0;
+
+                for _ in 0..*length {
+                    used += Self::inputs(
+                        variable,
+                        *element,
+                        location + used,
+                        types,
+                        constants,
+                        inputs,
+                    )?;
+                }
+                Ok(used)
+            }
+            _ => Err(unsupported()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{AbstractPipeline, Binding, Pipeline, Set};
+    use crate::kit::{shape2d, sprite2d, sprite2d_instanced};
+
+    const OP_FUNCTION: u32 = 54;
+
+    /// A minimal SPIR-V assembler, for building modules by hand.
+    struct Asm(Vec<u32>);
+
+    impl Asm {
+        fn new() -> Self {
+            Self(vec![MAGIC, 0x0001_0000, 0, 64, 0])
+        }
+
+        fn op(mut self, opcode: u32, operands: &[u32]) -> Self {
+            self.0.push((operands.len() as u32 + 1) << 16 | opcode);
+            self.0.extend_from_slice(operands);
+            self
+        }
+
+        fn entry_point(self, model: u32, name: &str, interface: &[u32]) -> Self {
+            let mut bytes = name.as_bytes().to_vec();
+            bytes.resize(name.len() / 4 * 4 + 4, 0);
+
+            let mut operands = vec![model, 1];
+            operands.extend(
+                bytes
+                    .chunks(4)
+                    .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]])),
+            );
+            operands.extend_from_slice(interface);
+
+            self.op(OP_ENTRY_POINT, &operands)
+        }
+
+        /// Declare `float` as `%2`, `vec2` as `%3`, `vec4` as `%4`, `mat4` as
+        /// `%5`, `uint` as `%6`, the constant `2` as `%7` and `vec4[2]` as `%8`.
+        fn types(self) -> Self {
+            self.op(OP_TYPE_FLOAT, &[2, 32])
+                .op(OP_TYPE_VECTOR, &[3, 2, 2])
+                .op(OP_TYPE_VECTOR, &[4, 2, 4])
+                .op(OP_TYPE_MATRIX, &[5, 4, 4])
+                .op(OP_TYPE_INT, &[6, 32, 0])
+                .op(OP_CONSTANT, &[6, 7, 2])
+                .op(OP_TYPE_ARRAY, &[8, 4, 7])
+        }
+
+        /// Declare an input variable `id` of type `ty` at `location`.
+        fn input(self, id: u32, ty: u32, location: u32) -> Self {
+            self.op(OP_DECORATE, &[id, DECORATION_LOCATION, location])
+                .op(OP_TYPE_POINTER, &[id + 1, STORAGE_INPUT, ty])
+                .op(OP_VARIABLE, &[id + 1, id, STORAGE_INPUT])
+        }
+
+        /// Declare a block variable `id` holding a single `vec4`, bound at
+        /// `set` and `binding`.
+        fn block(self, id: u32, storage: u32, decoration: u32, set: u32, binding: u32) -> Self {
+            self.op(OP_DECORATE, &[id + 1, decoration])
+                .op(OP_DECORATE, &[id, DECORATION_DESCRIPTOR_SET, set])
+                .op(OP_DECORATE, &[id, DECORATION_BINDING, binding])
+                .op(OP_TYPE_STRUCT, &[id + 1, 4])
+                .op(OP_TYPE_POINTER, &[id + 2, storage, id + 1])
+                .op(OP_VARIABLE, &[id + 2, id, storage])
+        }
+
+        fn bytes(&self) -> Vec<u8> {
+            self.0
+                .iter()
+                .flat_map(|w| w.to_le_bytes().to_vec())
+                .collect()
+        }
+    }
+
+    fn vertex(inputs: &[u32]) -> Asm {
+        Asm::new().entry_point(EXECUTION_MODEL_VERTEX, "main", inputs)
+    }
+
+    fn fragment() -> Asm {
+        Asm::new().entry_point(EXECUTION_MODEL_FRAGMENT, "main", &[])
+    }
+
+    fn description<'a>(
+        vertex_layout: &'a [VertexFormat],
+        pipeline_layout: &'a [Set<'a>],
+    ) -> PipelineDescription<'a> {
+        PipelineDescription {
+            vertex_layout,
+            pipeline_layout,
+            ..Pipeline::description()
+        }
+    }
+
+    fn validate_with(desc: &PipelineDescription, vs: Asm, fs: Asm) -> Result<(), Error> {
+        validate(desc, &vs.bytes(), &fs.bytes())
+    }
+
+    #[test]
+    fn kit_shaders() {
+        for desc in &[
+            shape2d::Pipeline::description(),
+            sprite2d::Pipeline::description(),
+            sprite2d_instanced::Pipeline::description(),
+        ] {
+            validate(desc, desc.vertex_shader, desc.fragment_shader).unwrap();
+        }
+    }
+
+    #[test]
+    fn kit_shader_inputs() {
+        let desc = sprite2d_instanced::Pipeline::description();
+        let module = Module::parse(desc.vertex_shader).unwrap();
+        let locations: Vec<_> = module.inputs.iter().map(|i| i.location).collect();
+
+        assert_eq!(locations, (0..9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn wrong_set() {
+        let vs = vertex(&[])
+            .types()
+            .block(10, STORAGE_UNIFORM, DECORATION_BLOCK, 1, 0);
+        let layout = [Set(&[Binding {
+            binding: BindingType::UniformBuffer,
+            stage: ShaderStage::Vertex,
+        }])];
+
+        let err = validate_with(&description(&[], &layout), vs, fragment()).unwrap_err();
+
+        assert!(
+            err.to_string().contains("set 1, binding 0 is missing"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn wrong_format() {
+        let vs = vertex(&[10]).types().input(10, 4, 0);
+        let desc = description(&[VertexFormat::Float2], &[]);
+
+        let err = validate_with(&desc, vs, fragment()).unwrap_err();
+
+        assert!(err.to_string().contains("location 0"), "{}", err);
+        assert!(validate_with(
+            &description(&[VertexFormat::Float4], &[]),
+            vertex(&[10]).types().input(10, 4, 0),
+            fragment()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn matrix_and_array_locations() {
+        let vs = vertex(&[10, 12, 14])
+            .types()
+            .input(10, 3, 0)
+            .input(12, 5, 1)
+            .input(14, 8, 5);
+        let module = Module::parse(&vs.bytes()).unwrap();
+        let inputs: Vec<_> = module
+            .inputs
+            .iter()
+            .map(|i| (i.variable, i.location, i.components))
+            .collect();
+
+        assert_eq!(
+            inputs,
+            vec![
+                (10, 0, 2),
+                (12, 1, 4),
+                (12, 2, 4),
+                (12, 3, 4),
+                (12, 4, 4),
+                (14, 5, 4),
+                (14, 6, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn buffer_block_storage() {
+        let module = Module::parse(
+            &vertex(&[])
+                .types()
+                .block(10, STORAGE_UNIFORM, DECORATION_BUFFER_BLOCK, 0, 0)
+                .block(20, STORAGE_UNIFORM, DECORATION_BUFFER_BLOCK, 0, 1)
+                .op(OP_MEMBER_DECORATE, &[21, 0, DECORATION_NON_WRITABLE])
+                .block(30, STORAGE_UNIFORM, DECORATION_BLOCK, 0, 2)
+                .bytes(),
+        )
+        .unwrap();
+        let resources: Vec<_> = module.descriptors.iter().map(|d| &d.resource).collect();
+
+        assert_eq!(
+            resources,
+            vec![
+                &Resource::StorageBuffer { readonly: false },
+                &Resource::StorageBuffer { readonly: true },
+                &Resource::UniformBuffer,
+            ]
+        );
+    }
+
+    #[test]
+    fn binding_used_by_both_stages() {
+        let vs = vertex(&[])
+            .types()
+            .block(10, STORAGE_UNIFORM, DECORATION_BLOCK, 0, 0);
+        let fs = fragment()
+            .types()
+            .block(10, STORAGE_UNIFORM, DECORATION_BLOCK, 0, 0);
+        let layout = [Set(&[Binding {
+            binding: BindingType::UniformBuffer,
+            stage: ShaderStage::Vertex,
+        }])];
+
+        let err = validate_with(&description(&[], &layout), vs, fs).unwrap_err();
+
+        assert!(err.to_string().contains("used by both"), "{}", err);
+    }
+
+    #[test]
+    fn inputs_of_other_entry_points() {
+        let vs = vertex(&[10])
+            .entry_point(EXECUTION_MODEL_VERTEX, "other", &[12])
+            .types()
+            .input(10, 3, 0)
+            .input(12, 4, 0);
+        let desc = description(&[VertexFormat::Float2], &[]);
+
+        assert!(validate_with(&desc, vs, fragment()).is_ok());
+    }
+
+    #[test]
+    fn truncated_module() {
+        let mut bytes = vertex(&[10]).types().input(10, 3, 0).bytes();
+
+        bytes.truncate(bytes.len() - 4);
+        assert!(matches!(Module::parse(&bytes), Err(Error::InvalidSpirv(_))));
+
+        bytes.truncate(bytes.len() - 2);
+        assert!(matches!(Module::parse(&bytes), Err(Error::InvalidSpirv(_))));
+
+        assert!(Module::parse(&bytes[..12]).is_err());
+        assert!(Module::parse(&[0; 20]).is_err());
+        assert!(Module::parse(&Asm::new().op(OP_FUNCTION, &[]).bytes()).is_ok());
+    }
+}
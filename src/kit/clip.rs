@@ -0,0 +1,331 @@
+#![deny(clippy::all, clippy::use_self)]
+
+use std::cell::Cell;
+use std::ops::{Deref, DerefMut};
+
+use crate::core;
+use crate::core::{
+    AbstractPipeline, Blending, ColorTarget, ColorWrite, CompareFunction, DepthFormat,
+    DepthStencilState, StencilFace, StencilOp,
+};
+
+use crate::math::*;
+
+use crate::kit::shape2d;
+
+///////////////////////////////////////////////////////////////////////////
+// Clipped
+///////////////////////////////////////////////////////////////////////////
+
+/// A pipeline whose output is clipped to the current mask of a [`Clip`]
+/// stack, eg. `Clipped<sprite2d::Pipeline>`.
+///
+/// Clipped pipelines have a stencil test, and can only be used in passes
+/// with a [`core::DepthBuffer`] of format [`Clip::FORMAT`] attached.
+pub struct Clipped<P>(pub P);
+
+impl<P> Deref for Clipped<P> {
+    type Target = P;
+
+    fn deref(&self) -> &P {
+        &self.0
+    }
+}
+
+impl<P> DerefMut for Clipped<P> {
+    fn deref_mut(&mut self) -> &mut P {
+        &mut self.0
+    }
+}
+
+impl<'a, P> AbstractPipeline<'a> for Clipped<P>
+where
+    P: AbstractPipeline<'a>,
+{
+    type PrepareContext = P::PrepareContext;
+    type Uniforms = P::Uniforms;
+
+    fn description() -> core::PipelineDescription<'a> {
+        core::PipelineDescription {
+            depth_stencil: Some(Clip::state(StencilOp::Keep)),
+            ..P::description()
+        }
+    }
+
+    fn setup(pipeline: core::Pipeline, dev: &core::Device, w: u32, h: u32) -> Self {
+        Self(P::setup(pipeline, dev, w, h))
+    }
+
+    fn apply(&self, pass: &mut core::Pass) {
+        self.0.apply(pass)
+    }
+
+    fn resize(&mut self, w: u32, h: u32) {
+        self.0.resize(w, h)
+    }
+
+    fn width(&self) -> u32 {
+        self.0.width()
+    }
+
+    fn height(&self) -> u32 {
+        self.0.height()
+    }
+
+    fn prepare(
+        &'a self,
+        ctx: Self::PrepareContext,
+    ) -> Option<(&'a core::UniformBuffer, Vec<Self::Uniforms>)> {
+        self.0.prepare(ctx)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Masks
+///////////////////////////////////////////////////////////////////////////
+
+/// The color target of mask pipelines, which leaves color and alpha untouched.
+const MASK_TARGET: ColorTarget = ColorTarget {
+    format: core::SwapChain::FORMAT,
+    blending: Blending::constant(),
+    write_mask: ColorWrite::NONE,
+};
+
+/// A shape pipeline which only writes to the stencil buffer, incrementing it
+/// where shapes are drawn.
+struct Push(shape2d::Pipeline);
+
+/// A shape pipeline which only writes to the stencil buffer, decrementing it
+/// where shapes are drawn.
+struct Pop(shape2d::Pipeline);
+
+macro_rules! mask_pipeline {
+    ($name:ident, $op:expr) => {
+        impl<'a> AbstractPipeline<'a> for $name {
+            type PrepareContext = Matrix4<f32>;
+            type Uniforms = shape2d::Uniforms;
+
+            fn description() -> core::PipelineDescription<'a> {
+                core::PipelineDescription {
+                    targets: &[MASK_TARGET],
+                    depth_stencil: Some(Clip::state($op)),
+                    ..shape2d::Pipeline::description()
+                }
+            }
+
+            fn setup(pipeline: core::Pipeline, dev: &core::Device, w: u32, h: u32) -> Self {
+                Self(shape2d::Pipeline::setup(pipeline, dev, w, h))
+            }
+
+            fn apply(&self, pass: &mut core::Pass) {
+                self.0.apply(pass)
+            }
+
+            fn resize(&mut self, w: u32, h: u32) {
+                self.0.resize(w, h)
+            }
+
+            fn width(&self) -> u32 {
+                self.0.width()
+            }
+
+            fn height(&self) -> u32 {
+                self.0.height()
+            }
+
+            fn prepare(
+                &'a self,
+                transform: Matrix4<f32>,
+            ) -> Option<(&'a core::UniformBuffer, Vec<shape2d::Uniforms>)> {
+                self.0.prepare(transform)
+            }
+        }
+    };
+}
+
+mask_pipeline!(Push, StencilOp::IncrementClamp);
+mask_pipeline!(Pop, StencilOp::DecrementClamp);
+
+///////////////////////////////////////////////////////////////////////////
+// Clip
+///////////////////////////////////////////////////////////////////////////
+
+/// A stack of clip masks, built from [`shape2d`] meshes.
+///
+/// Each mask is drawn into the stencil buffer, where it is intersected with
+/// the masks below it. [`Clipped`] pipelines then only draw where all masks
+/// on the stack overlap.
+///
+/// Pushing or popping a mask changes the pass's pipeline, so pipelines have
+/// to be set again before drawing.
+pub struct Clip {
+    push: Push,
+    pop: Pop,
+    depth: Depth,
+}
+
+impl Clip {
+    /// The depth/stencil format of passes clipped with a [`Clip`] stack.
+    pub const FORMAT: DepthFormat = DepthFormat::Depth24PlusStencil8;
+
+    /// The deepest masks can be nested, limited by the stencil's bit depth.
+    pub const MAX_DEPTH: u32 = 255;
+
+    pub fn new(r: &core::Renderer, w: u32, h: u32) -> Self {
+        Self {
+            push: Push(r.pipeline(w, h, Blending::default())),
+            pop: Pop(r.pipeline(w, h, Blending::default())),
+            depth: Depth::default(),
+        }
+    }
+
+    /// The number of masks currently on the stack.
+    pub fn depth(&self) -> u32 {
+        self.depth.0.get()
+    }
+
+    pub fn resize(&mut self, w: u32, h: u32) {
+        self.push.resize(w, h);
+        self.pop.resize(w, h);
+    }
+
+    /// Update the transform masks are drawn with.
+    pub fn update(&self, r: &mut core::Renderer, transform: Matrix4<f32>, f: &mut core::Frame) {
+        r.update_pipeline(&self.push, transform, f);
+        r.update_pipeline(&self.pop, transform, f);
+    }
+
+    /// Push a mask onto the stack. Subsequent draws with [`Clipped`]
+    /// pipelines are clipped to the intersection of this mask with the ones
+    /// below it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than [`Clip::MAX_DEPTH`] masks are pushed.
+    pub fn push(&self, pass: &mut core::Pass, mask: &core::Mesh<shape2d::Vertex>) {
+        let depth = self.depth.push();
+
+        pass.set_pipeline(&self.push);
+        pass.set_stencil_reference(depth);
+        pass.draw_mesh(mask);
+        pass.set_stencil_reference(depth + 1);
+    }
+
+    /// Pop the topmost mask off the stack. This must be the same mask that
+    /// was last pushed, as it is drawn again to undo it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stack is empty.
+    pub fn pop(&self, pass: &mut core::Pass, mask: &core::Mesh<shape2d::Vertex>) {
+        let depth = self.depth.pop();
+
+        pass.set_pipeline(&self.pop);
+        pass.set_stencil_reference(depth);
+        pass.draw_mesh(mask);
+        pass.set_stencil_reference(depth - 1);
+    }
+
+    /// Push a mask, run `inner`, and pop the mask again.
+    pub fn with<F>(&self, pass: &mut core::Pass, mask: &core::Mesh<shape2d::Vertex>, inner: F)
+    where
+        F: FnOnce(&mut core::Pass),
+    {
+        self.push(pass, mask);
+        inner(pass);
+        self.pop(pass, mask);
+    }
+
+    /// The stencil state of pipelines used with a clip stack: fragments only
+    /// pass where the stencil equals the current depth, and `op` is applied to
+    /// the stencil where they do.
+    fn state(op: StencilOp) -> DepthStencilState {
+        DepthStencilState::new(Self::FORMAT)
+            .depth_write(false)
+            .depth_compare(CompareFunction::Always)
+            .stencil(StencilFace {
+                compare: CompareFunction::Equal,
+                fail_op: StencilOp::Keep,
+                depth_fail_op: StencilOp::Keep,
+                pass_op: op,
+            })
+    }
+}
+
+/// The number of masks on a clip stack. Fragments inside all of them have
+/// this as their stencil value.
+#[derive(Default)]
+struct Depth(Cell<u32>);
+
+impl Depth {
+    /// Add a mask, returning the depth before it was added.
+    fn push(&self) -> u32 {
+        let depth = self.0.get();
+        assert!(depth < Clip::MAX_DEPTH, "clip stack overflow");
+
+        self.0.set(depth + 1);
+        depth
+    }
+
+    /// Remove a mask, returning the depth before it was removed.
+    fn pop(&self) -> u32 {
+        let depth = self.0.get();
+        assert!(depth > 0, "clip stack underflow");
+
+        self.0.set(depth - 1);
+        depth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn depth() {
+        let depth = Depth::default();
+
+        assert_eq!(depth.push(), 0);
+        assert_eq!(depth.push(), 1);
+        assert_eq!(depth.pop(), 2);
+        assert_eq!(depth.push(), 1);
+        assert_eq!(depth.pop(), 2);
+        assert_eq!(depth.pop(), 1);
+        assert_eq!(depth.0.get(), 0);
+    }
+
+    #[test]
+    fn max_depth() {
+        let depth = Depth::default();
+
+        for i in 0..Clip::MAX_DEPTH {
+            assert_eq!(depth.push(), i);
+        }
+        assert_eq!(depth.0.get(), Clip::MAX_DEPTH);
+    }
+
+    #[test]
+    #[should_panic(expected = "clip stack overflow")]
+    fn overflow() {
+        let depth = Depth::default();
+
+        for _ in 0..=Clip::MAX_DEPTH {
+            depth.push();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "clip stack underflow")]
+    fn underflow() {
+        Depth::default().pop();
+    }
+
+    #[test]
+    fn masks_write_no_color() {
+        for desc in &[Push::description(), Pop::description()] {
+            assert_eq!(desc.targets, &[MASK_TARGET]);
+            assert_eq!(desc.targets[0].format, desc.format);
+            assert_eq!(desc.targets[0].write_mask, ColorWrite::NONE);
+        }
+    }
+}
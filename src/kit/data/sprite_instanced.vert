@@ -0,0 +1,39 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Globals {
+	mat4 ortho;
+	mat4 transform;
+} global;
+
+layout(set = 1, binding = 0) uniform Model {
+	mat4 transform;
+} model;
+
+// Per-vertex: the corner of the shared unit quad.
+layout(location = 0) in vec2  corner;
+
+// Per-instance.
+layout(location = 1) in vec4  src;
+layout(location = 2) in vec4  dst;
+layout(location = 3) in vec4  color;
+layout(location = 4) in float opacity;
+// The columns of the instance transform.
+layout(location = 5) in vec4  transform0;
+layout(location = 6) in vec4  transform1;
+layout(location = 7) in vec4  transform2;
+layout(location = 8) in vec4  transform3;
+
+layout(location = 0) out vec2  f_uv;
+layout(location = 1) out vec4  f_color;
+layout(location = 2) out float f_opacity;
+
+void main() {
+	mat4 transform = mat4(transform0, transform1, transform2, transform3);
+	vec2 position = mix(dst.xy, dst.zw, corner);
+
+	f_color = color;
+	f_uv = vec2(mix(src.x, src.z, corner.x), mix(src.w, src.y, corner.y));
+	f_opacity = opacity;
+
+	gl_Position = global.ortho * global.transform * model.transform * transform * vec4(position, 0.0, 1.0);
+}
@@ -2,8 +2,10 @@
 pub use crate::core;
 pub use crate::core::{Bgra8, Rgba, Rgba8};
 
+pub mod clip;
 pub mod shape2d;
 pub mod sprite2d;
+pub mod sprite2d_instanced;
 
 use crate::math::{Matrix4, Ortho};
 
@@ -156,6 +158,8 @@ pub fn ortho(w: u32, h: u32) -> Matrix4<f32> {
 
 ///////////////////////////////////////////////////////////////////////////////
 
+#[deprecated(note = "use `core::DynamicBuffer`, which pads its entries")]
+#[allow(deprecated)]
 #[derive(Copy, Clone)]
 pub struct AlignedBuffer {
     // TODO: Make this generic when rust-lang#43408 is fixed.
@@ -163,8 +167,9 @@ pub struct AlignedBuffer {
     padding: [u8; AlignedBuffer::PAD],
 }
 
+#[allow(deprecated)]
 impl AlignedBuffer {
-    pub const ALIGNMENT: u64 = 256;
+    pub const ALIGNMENT: u64 = core::BUFFER_ALIGNMENT;
     pub const PAD: usize = Self::ALIGNMENT as usize - std::mem::size_of::<Matrix4<f32>>();
 
     pub fn new(data: Matrix4<f32>) -> Self {
@@ -175,30 +180,40 @@ impl AlignedBuffer {
     }
 }
 
-struct Model {
-    buf: core::UniformBuffer,
-    binding: core::BindingGroup,
-    size: usize,
+/// Model transforms of a kit pipeline, one of which is bound when drawing.
+///
+/// Pipelines are set up with a single identity transform. To draw with
+/// different transforms, create a model with one transform per draw, update
+/// it every frame with [`core::Renderer::update_dynamic_buffer`], and select
+/// a transform with [`Model::bind`].
+pub struct Model {
+    pub buf: core::DynamicBuffer<Matrix4<f32>>,
+    pub binding: core::BindingGroup,
 }
 
 impl Model {
-    fn new(
+    pub fn new(
         layout: &core::BindingGroupLayout,
         transforms: &[Matrix4<f32>],
         dev: &core::Device,
     ) -> Self {
-        let aligned = Self::aligned(transforms);
-        let buf = dev.create_uniform_buffer(aligned.as_slice());
+        let buf = dev.create_dynamic_buffer(transforms);
         let binding = dev.create_binding_group(&layout, &[&buf]);
-        let size = transforms.len();
-        Self { buf, binding, size }
+        Self { buf, binding }
     }
 
-    fn aligned(transforms: &[Matrix4<f32>]) -> Vec<AlignedBuffer> {
-        let mut aligned = Vec::with_capacity(transforms.len());
-        for t in transforms {
-            aligned.push(AlignedBuffer::new(*t));
-        }
-        aligned
+    /// The number of transforms in the model.
+    pub fn len(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bind the transform at `index` for subsequent draws. The pipeline the
+    /// model was created for must be set on the pass.
+    pub fn bind(&self, pass: &mut core::Pass, index: usize) {
+        pass.set_dynamic_binding(&self.binding, &self.buf, index);
     }
 }
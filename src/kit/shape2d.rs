@@ -6,7 +6,7 @@ use std::f32;
 use crate::math::*;
 
 use crate::core;
-use crate::core::{Binding, BindingType, Rect, Rgba, Set, ShaderStage};
+use crate::core::{Binding, BindingType, Rect, Rgba, Set, ShaderStage, Vertex as _};
 
 use crate::kit;
 use crate::kit::{Model, Rgba8};
@@ -42,6 +42,11 @@ impl Vertex {
     }
 }
 
+impl core::Vertex for Vertex {
+    const FORMATS: &'static [core::VertexFormat] =
+        &[core::VertexFormat::Float2, core::VertexFormat::UByte4];
+}
+
 #[inline]
 pub const fn vertex(x: f32, y: f32, color: Rgba8) -> Vertex {
     Vertex::new(x, y, color)
@@ -61,6 +66,14 @@ pub struct Pipeline {
     model: Model,
 }
 
+impl Pipeline {
+    /// Create a [`Model`] with the given transforms, to draw with
+    /// [`Model::bind`] instead of the pipeline's identity transform.
+    pub fn model(&self, renderer: &core::Renderer, transforms: &[Matrix4<f32>]) -> Model {
+        Model::new(&self.pipeline.layout.sets[1], transforms, &renderer.device)
+    }
+}
+
 //////////////////////////////////////////////////////////////////////////
 
 impl<'a> core::AbstractPipeline<'a> for Pipeline {
@@ -69,20 +82,28 @@ impl<'a> core::AbstractPipeline<'a> for Pipeline {
 
     fn description() -> core::PipelineDescription<'a> {
         core::PipelineDescription {
-            vertex_layout: &[core::VertexFormat::Float2, core::VertexFormat::UByte4],
+            vertex_layout: Vertex::FORMATS,
+            vertex_stride: None,
+            vertex_slots: &[],
             pipeline_layout: &[
                 Set(&[Binding {
                     binding: BindingType::UniformBuffer,
                     stage: ShaderStage::Vertex,
                 }]),
                 Set(&[Binding {
-                    binding: BindingType::UniformBuffer,
+                    binding: BindingType::UniformBufferDynamic,
                     stage: ShaderStage::Vertex,
                 }]),
             ],
             // TODO: Use `env("CARGO_MANIFEST_DIR")`
             vertex_shader: include_bytes!("data/shape.vert.spv"),
             fragment_shader: include_bytes!("data/shape.frag.spv"),
+            format: core::SwapChain::FORMAT,
+            index_format: core::IndexFormat::Uint32,
+            targets: &[],
+            depth_stencil: None,
+            primitive: core::PrimitiveState::default(),
+            entry_points: core::EntryPoints::MAIN,
         }
     }
 
@@ -121,7 +142,7 @@ impl<'a> core::AbstractPipeline<'a> for Pipeline {
     fn apply(&self, pass: &mut core::Pass) {
         pass.set_pipeline(&self.pipeline);
         pass.set_binding(&self.bindings, &[]);
-        pass.set_binding(&self.model.binding, &[]);
+        self.model.bind(pass, 0);
     }
 
     fn prepare(
@@ -174,7 +195,8 @@ pub enum Shape {
 }
 
 impl Shape {
-    pub fn triangulate(&self) -> Vec<Vertex> {
+    /// Triangulate the shape into vertices, and indices into those vertices.
+    pub fn triangulate(&self) -> (Vec<Vertex>, Vec<u32>) {
         match *self {
             Shape::Line(l, Stroke { width, color }) => {
                 let v = (l.p2 - l.p1).normalize();
@@ -183,127 +205,114 @@ impl Shape {
                 let wy = width / 2.0 * v.x;
                 let rgba8 = color.into();
 
-                vec![
-                    vertex(l.p1.x - wx, l.p1.y + wy, rgba8),
-                    vertex(l.p1.x + wx, l.p1.y - wy, rgba8),
-                    vertex(l.p2.x - wx, l.p2.y + wy, rgba8),
-                    vertex(l.p2.x - wx, l.p2.y + wy, rgba8),
-                    vertex(l.p1.x + wx, l.p1.y - wy, rgba8),
-                    vertex(l.p2.x + wx, l.p2.y - wy, rgba8),
-                ]
+                (
+                    vec![
+                        vertex(l.p1.x - wx, l.p1.y + wy, rgba8),
+                        vertex(l.p1.x + wx, l.p1.y - wy, rgba8),
+                        vertex(l.p2.x - wx, l.p2.y + wy, rgba8),
+                        vertex(l.p2.x + wx, l.p2.y - wy, rgba8),
+                    ],
+                    vec![0, 1, 2, 2, 1, 3],
+                )
             }
             Shape::Rectangle(r, stroke, fill) => {
                 let width = stroke.width;
                 let inner = Rect::new(r.x1 + width, r.y1 + width, r.x2 - width, r.y2 - width);
 
-                let mut verts = if stroke != Stroke::NONE {
+                let (mut verts, mut indices) = if stroke != Stroke::NONE {
                     let rgba8 = stroke.color.into();
 
                     let outer = r;
 
-                    vec![
-                        // Bottom
-                        vertex(outer.x1, outer.y1, rgba8),
-                        vertex(outer.x2, outer.y1, rgba8),
-                        vertex(inner.x1, inner.y1, rgba8),
-                        vertex(inner.x1, inner.y1, rgba8),
-                        vertex(outer.x2, outer.y1, rgba8),
-                        vertex(inner.x2, inner.y1, rgba8),
-                        // Left
-                        vertex(outer.x1, outer.y1, rgba8),
-                        vertex(inner.x1, inner.y1, rgba8),
-                        vertex(outer.x1, outer.y2, rgba8),
-                        vertex(outer.x1, outer.y2, rgba8),
-                        vertex(inner.x1, inner.y1, rgba8),
-                        vertex(inner.x1, inner.y2, rgba8),
-                        // Right
-                        vertex(inner.x2, inner.y1, rgba8),
-                        vertex(outer.x2, outer.y1, rgba8),
-                        vertex(outer.x2, outer.y2, rgba8),
-                        vertex(inner.x2, inner.y1, rgba8),
-                        vertex(inner.x2, inner.y2, rgba8),
-                        vertex(outer.x2, outer.y2, rgba8),
-                        // Top
-                        vertex(outer.x1, outer.y2, rgba8),
-                        vertex(outer.x2, outer.y2, rgba8),
-                        vertex(inner.x1, inner.y2, rgba8),
-                        vertex(inner.x1, inner.y2, rgba8),
-                        vertex(outer.x2, outer.y2, rgba8),
-                        vertex(inner.x2, inner.y2, rgba8),
-                    ]
+                    #[rustfmt::skip]
+                    let indices = vec![
+                        0, 1, 4, 4, 1, 5, // Bottom
+                        0, 4, 3, 3, 4, 7, // Left
+                        5, 1, 2, 5, 6, 2, // Right
+                        3, 2, 7, 7, 2, 6, // Top
+                    ];
+                    (
+                        vec![
+                            // Outer
+                            vertex(outer.x1, outer.y1, rgba8),
+                            vertex(outer.x2, outer.y1, rgba8),
+                            vertex(outer.x2, outer.y2, rgba8),
+                            vertex(outer.x1, outer.y2, rgba8),
+                            // Inner
+                            vertex(inner.x1, inner.y1, rgba8),
+                            vertex(inner.x2, inner.y1, rgba8),
+                            vertex(inner.x2, inner.y2, rgba8),
+                            vertex(inner.x1, inner.y2, rgba8),
+                        ],
+                        indices,
+                    )
                 } else {
-                    Vec::with_capacity(6)
+                    (Vec::with_capacity(4), Vec::with_capacity(6))
                 };
 
                 match fill {
                     Fill::Solid(color) => {
                         let rgba8 = color.into();
+                        let n = verts.len() as u32;
 
                         verts.extend_from_slice(&[
                             vertex(inner.x1, inner.y1, rgba8),
                             vertex(inner.x2, inner.y1, rgba8),
                             vertex(inner.x2, inner.y2, rgba8),
-                            vertex(inner.x1, inner.y1, rgba8),
                             vertex(inner.x1, inner.y2, rgba8),
-                            vertex(inner.x2, inner.y2, rgba8),
                         ]);
+                        indices.extend_from_slice(&[n, n + 1, n + 2, n, n + 3, n + 2]);
                     }
                     Fill::Gradient(_, _) => {
                         unimplemented!();
                     }
                     Fill::Empty() => {}
                 }
-                verts
+                (verts, indices)
             }
             Shape::Circle(position, radius, sides, stroke, fill) => {
                 let inner = Self::circle(position, radius - stroke.width, sides);
+                let n = inner.len() as u32 - 1;
 
-                let mut verts = if stroke != Stroke::NONE {
+                let (mut verts, mut indices) = if stroke != Stroke::NONE {
                     // If there is a stroke, the outer circle is larger.
                     let outer = Self::circle(position, radius, sides);
                     let rgba8 = stroke.color.into();
 
-                    let n = inner.len() - 1;
-                    let mut vs = Vec::with_capacity(n * 6);
+                    // Inner vertices are at `i`, and outer ones at `o`.
+                    let mut verts = Vec::with_capacity(inner.len() * 2);
+                    let mut indices = Vec::with_capacity(n as usize * 6);
+                    verts.extend(inner.iter().map(|p| vertex(p.x, p.y, rgba8)));
+                    verts.extend(outer.iter().map(|p| vertex(p.x, p.y, rgba8)));
+
                     for i in 0..n {
-                        let (i0, i1) = (inner[i], inner[i + 1]);
-                        let (o0, o1) = (outer[i], outer[i + 1]);
-
-                        vs.extend_from_slice(&[
-                            vertex(i0.x, i0.y, rgba8),
-                            vertex(o0.x, o0.y, rgba8),
-                            vertex(o1.x, o1.y, rgba8),
-                            vertex(i0.x, i0.y, rgba8),
-                            vertex(o1.x, o1.y, rgba8),
-                            vertex(i1.x, i1.y, rgba8),
-                        ]);
+                        let o = i + n + 1;
+                        indices.extend_from_slice(&[i, o, o + 1, i, o + 1, i + 1]);
                     }
-                    vs
+                    (verts, indices)
                 } else {
-                    Vec::new()
+                    (Vec::new(), Vec::new())
                 };
 
                 match fill {
                     Fill::Solid(color) => {
                         let rgba8 = color.into();
-                        let center = Vertex::new(position.x, position.y, rgba8);
-                        let inner_verts: Vec<Vertex> =
-                            inner.iter().map(|p| Vertex::new(p.x, p.y, rgba8)).collect();
-                        for i in 0..sides as usize {
-                            verts.extend_from_slice(&[center, inner_verts[i], inner_verts[i + 1]]);
+                        let center = verts.len() as u32;
+
+                        verts.push(Vertex::new(position.x, position.y, rgba8));
+                        verts.extend(inner.iter().map(|p| Vertex::new(p.x, p.y, rgba8)));
+
+                        for i in 0..n {
+                            let i = center + 1 + i;
+                            indices.extend_from_slice(&[center, i, i + 1]);
                         }
-                        verts.extend_from_slice(&[
-                            center,
-                            *inner_verts.last().unwrap(),
-                            *inner_verts.first().unwrap(),
-                        ]);
                     }
                     Fill::Gradient(_, _) => {
                         unimplemented!();
                     }
                     Fill::Empty() => {}
                 }
-                verts
+                (verts, indices)
             }
         }
     }
@@ -361,16 +370,29 @@ impl Batch {
         self.items.push(shape);
     }
 
+    /// The vertices of all shapes in the batch, as a non-indexed triangle list.
+    #[deprecated(note = "use `geometry`, which returns indexed vertices")]
     pub fn vertices(&self) -> Vec<Vertex> {
+        let (verts, indices) = self.geometry();
+
+        indices.into_iter().map(|i| verts[i as usize]).collect()
+    }
+
+    /// The vertices of all shapes in the batch, and indices into them.
+    pub fn geometry(&self) -> (Vec<Vertex>, Vec<u32>) {
         // TODO: This is a lower-bound estimate of how much space we need.
         // We should get the actual numbers from the shapes.
-        let mut buf = Vec::with_capacity(6 * self.items.len());
+        let mut verts = Vec::with_capacity(4 * self.items.len());
+        let mut indices = Vec::with_capacity(6 * self.items.len());
 
         for shape in self.items.iter() {
-            let mut verts: Vec<Vertex> = shape.triangulate();
-            buf.append(&mut verts);
+            let (vs, is) = shape.triangulate();
+            let offset = verts.len() as u32;
+
+            verts.extend(vs);
+            indices.extend(is.into_iter().map(|i| i + offset));
         }
-        buf
+        (verts, indices)
     }
 
     pub fn is_empty(&self) -> bool {
@@ -381,12 +403,120 @@ impl Batch {
         self.items.clear();
     }
 
-    pub fn buffer(&self, r: &core::Renderer) -> core::VertexBuffer {
-        let buf = self.vertices();
-        r.device.create_buffer(buf.as_slice())
+    pub fn buffer(&self, r: &core::Renderer) -> core::Mesh<Vertex> {
+        let (verts, indices) = self.geometry();
+
+        core::Mesh {
+            vertices: r.device.create_buffer(verts.as_slice()),
+            indices: r.device.create_index_u32(indices.as_slice()),
+        }
     }
 
-    pub fn finish(self, r: &core::Renderer) -> core::VertexBuffer {
+    pub fn finish(self, r: &core::Renderer) -> core::Mesh<Vertex> {
         self.buffer(r)
     }
+
+    /// Upload the batch into an existing mesh, eg. the one of a previous
+    /// frame. The mesh's buffers are only reallocated if the batch doesn't fit.
+    pub fn upload(
+        &self,
+        r: &mut core::Renderer,
+        mesh: &mut core::Mesh<Vertex>,
+        f: &mut core::Frame,
+    ) {
+        let (verts, indices) = self.geometry();
+
+        r.update_mesh(mesh, verts.as_slice(), indices.as_slice(), f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check that the indices form triangles of the given vertices.
+    fn check((verts, indices): (Vec<Vertex>, Vec<u32>)) -> (usize, usize) {
+        assert_eq!(indices.len() % 3, 0);
+        assert!(indices.iter().all(|i| (*i as usize) < verts.len()));
+
+        (verts.len(), indices.len())
+    }
+
+    #[test]
+    fn line() {
+        let line = Shape::Line(Line::new(0., 0., 10., 0.), Stroke::new(2., Rgba::WHITE));
+        let (verts, indices) = line.triangulate();
+
+        assert_eq!(indices, vec![0, 1, 2, 2, 1, 3]);
+        assert_eq!(verts[0].position, Vector2::new(0., 1.));
+        assert_eq!(verts[3].position, Vector2::new(10., -1.));
+    }
+
+    #[test]
+    fn rectangle() {
+        let rect = Rect::new(0., 0., 10., 10.);
+        let stroke = Stroke::new(1., Rgba::WHITE);
+        let fill = Fill::Solid(Rgba::BLACK);
+
+        assert_eq!(
+            check(Shape::Rectangle(rect, stroke, Fill::Empty()).triangulate()),
+            (8, 24)
+        );
+        assert_eq!(
+            check(Shape::Rectangle(rect, Stroke::NONE, fill).triangulate()),
+            (4, 6)
+        );
+
+        let (verts, indices) = Shape::Rectangle(rect, stroke, fill).triangulate();
+
+        assert_eq!(check((verts.clone(), indices.clone())), (12, 30));
+        assert_eq!(&indices[24..], &[8, 9, 10, 8, 11, 10]);
+        assert_eq!(verts[8].position, Vector2::new(1., 1.));
+    }
+
+    #[test]
+    fn circle() {
+        let center = Point2::new(0., 0.);
+        let stroke = Stroke::new(1., Rgba::WHITE);
+        let fill = Fill::Solid(Rgba::BLACK);
+
+        assert_eq!(
+            check(Shape::Circle(center, 4., 8, stroke, Fill::Empty()).triangulate()),
+            (18, 48)
+        );
+        assert_eq!(
+            check(Shape::Circle(center, 4., 8, Stroke::NONE, fill).triangulate()),
+            (10, 24)
+        );
+        assert_eq!(
+            check(Shape::Circle(center, 4., 8, stroke, fill).triangulate()),
+            (28, 72)
+        );
+    }
+
+    #[test]
+    fn batch_geometry() {
+        let mut batch = Batch::new();
+
+        batch.add(Shape::Rectangle(
+            Rect::new(0., 0., 10., 10.),
+            Stroke::NONE,
+            Fill::Solid(Rgba::WHITE),
+        ));
+        batch.add(Shape::Rectangle(
+            Rect::new(10., 10., 20., 20.),
+            Stroke::NONE,
+            Fill::Solid(Rgba::WHITE),
+        ));
+        let (verts, indices) = batch.geometry();
+
+        assert_eq!(check((verts.clone(), indices.clone())), (8, 12));
+        assert_eq!(&indices[6..], &[4, 5, 6, 4, 7, 6]);
+
+        #[allow(deprecated)]
+        let expanded = batch.vertices();
+
+        assert_eq!(expanded.len(), 12);
+        assert_eq!(expanded[6].position, verts[4].position);
+    }
 }
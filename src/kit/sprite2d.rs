@@ -2,7 +2,7 @@
 #![allow(clippy::new_without_default)]
 
 use crate::core;
-use crate::core::{Binding, BindingType, Rect, Rgba, Set, ShaderStage};
+use crate::core::{Binding, BindingType, Rect, Rgba, Set, ShaderStage, Vertex as _};
 
 use crate::math::*;
 
@@ -46,6 +46,15 @@ impl Vertex {
     }
 }
 
+impl core::Vertex for Vertex {
+    const FORMATS: &'static [core::VertexFormat] = &[
+        core::VertexFormat::Float2,
+        core::VertexFormat::Float2,
+        core::VertexFormat::UByte4,
+        core::VertexFormat::Float,
+    ];
+}
+
 ///////////////////////////////////////////////////////////////////////////
 // Pipeline
 ///////////////////////////////////////////////////////////////////////////
@@ -71,11 +80,21 @@ impl Pipeline {
             .device
             .create_binding_group(&self.pipeline.layout.sets[2], &[texture, sampler])
     }
+
+    /// Create a [`Model`] with the given transforms, to draw with
+    /// [`Model::bind`] instead of the pipeline's identity transform.
+    pub fn model(&self, renderer: &core::Renderer, transforms: &[Matrix4<f32>]) -> Model {
+        Model::new(&self.pipeline.layout.sets[1], transforms, &renderer.device)
+    }
 }
 
 //////////////////////////////////////////////////////////////////////////
 
-pub struct Command<'a>(&'a core::VertexBuffer, &'a core::BindingGroup, Matrix4<f32>);
+pub struct Command<'a>(
+    &'a core::VertexBuffer<Vertex>,
+    &'a core::BindingGroup,
+    Matrix4<f32>,
+);
 
 pub struct Frame<'a> {
     commands: Vec<Command<'a>>,
@@ -83,7 +102,11 @@ pub struct Frame<'a> {
 }
 
 impl<'a> Frame<'a> {
-    pub fn draw(&mut self, buffer: &'a core::VertexBuffer, binding: &'a core::BindingGroup) {
+    pub fn draw(
+        &mut self,
+        buffer: &'a core::VertexBuffer<Vertex>,
+        binding: &'a core::BindingGroup,
+    ) {
         self.commands
             .push(Command(buffer, binding, *self.transforms.last()));
     }
@@ -120,19 +143,16 @@ impl<'a> core::AbstractPipeline<'a> for Pipeline {
 
     fn description() -> core::PipelineDescription<'a> {
         core::PipelineDescription {
-            vertex_layout: &[
-                core::VertexFormat::Float2,
-                core::VertexFormat::Float2,
-                core::VertexFormat::UByte4,
-                core::VertexFormat::Float,
-            ],
+            vertex_layout: Vertex::FORMATS,
+            vertex_stride: None,
+            vertex_slots: &[],
             pipeline_layout: &[
                 Set(&[Binding {
                     binding: BindingType::UniformBuffer,
                     stage: ShaderStage::Vertex,
                 }]),
                 Set(&[Binding {
-                    binding: BindingType::UniformBuffer,
+                    binding: BindingType::UniformBufferDynamic,
                     stage: ShaderStage::Vertex,
                 }]),
                 Set(&[
@@ -149,6 +169,12 @@ impl<'a> core::AbstractPipeline<'a> for Pipeline {
             // TODO: Use `env("CARGO_MANIFEST_DIR")`
             vertex_shader: include_bytes!("data/sprite.vert.spv"),
             fragment_shader: include_bytes!("data/sprite.frag.spv"),
+            format: core::SwapChain::FORMAT,
+            index_format: core::IndexFormat::Uint32,
+            targets: &[],
+            depth_stencil: None,
+            primitive: core::PrimitiveState::default(),
+            entry_points: core::EntryPoints::MAIN,
         }
     }
 
@@ -185,7 +211,7 @@ impl<'a> core::AbstractPipeline<'a> for Pipeline {
     fn apply(&self, pass: &mut core::Pass) {
         pass.set_pipeline(&self.pipeline);
         pass.set_binding(&self.bindings, &[]);
-        pass.set_binding(&self.model.binding, &[]);
+        self.model.bind(pass, 0);
     }
 
     fn prepare(
@@ -252,8 +278,18 @@ impl Batch {
         self.size += 1;
     }
 
+    /// The vertices of all sprites in the batch, as a non-indexed triangle list.
+    #[deprecated(note = "use `geometry`, which returns indexed vertices")]
     pub fn vertices(&self) -> Vec<Vertex> {
-        let mut buf = Vec::with_capacity(6 * self.items.len());
+        let (verts, indices) = self.geometry();
+
+        indices.into_iter().map(|i| verts[i as usize]).collect()
+    }
+
+    /// The vertices of all sprites in the batch, and indices into them.
+    pub fn geometry(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let mut verts = Vec::with_capacity(4 * self.items.len());
+        let mut indices = Vec::with_capacity(6 * self.items.len());
 
         for (src, dst, rgba, o, rep) in self.items.iter() {
             // Relative texture coordinates
@@ -263,23 +299,39 @@ impl Batch {
             let ry2: f32 = src.y2 / self.h as f32;
 
             let c: Rgba8 = (*rgba).into();
+            let n = verts.len() as u32;
 
-            // TODO: Use an index buffer
-            buf.extend_from_slice(&[
+            verts.extend_from_slice(&[
                 Vertex::new(dst.x1, dst.y1, rx1 * rep.x, ry2 * rep.y, c, *o),
                 Vertex::new(dst.x2, dst.y1, rx2 * rep.x, ry2 * rep.y, c, *o),
                 Vertex::new(dst.x2, dst.y2, rx2 * rep.x, ry1 * rep.y, c, *o),
-                Vertex::new(dst.x1, dst.y1, rx1 * rep.x, ry2 * rep.y, c, *o),
                 Vertex::new(dst.x1, dst.y2, rx1 * rep.x, ry1 * rep.y, c, *o),
-                Vertex::new(dst.x2, dst.y2, rx2 * rep.x, ry1 * rep.y, c, *o),
             ]);
+            indices.extend_from_slice(&[n, n + 1, n + 2, n, n + 3, n + 2]);
+        }
+        (verts, indices)
+    }
+
+    pub fn finish(self, r: &core::Renderer) -> core::Mesh<Vertex> {
+        let (verts, indices) = self.geometry();
+
+        core::Mesh {
+            vertices: r.device.create_buffer(verts.as_slice()),
+            indices: r.device.create_index_u32(indices.as_slice()),
         }
-        buf
     }
 
-    pub fn finish(self, r: &core::Renderer) -> core::VertexBuffer {
-        let buf = self.vertices();
-        r.device.create_buffer(buf.as_slice())
+    /// Upload the batch into an existing mesh, eg. the one of a previous
+    /// frame. The mesh's buffers are only reallocated if the batch doesn't fit.
+    pub fn upload(
+        &self,
+        r: &mut core::Renderer,
+        mesh: &mut core::Mesh<Vertex>,
+        f: &mut core::Frame,
+    ) {
+        let (verts, indices) = self.geometry();
+
+        r.update_mesh(mesh, verts.as_slice(), indices.as_slice(), f);
     }
 
     pub fn clear(&mut self) {
@@ -293,3 +345,38 @@ impl Batch {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geometry() {
+        let mut batch = Batch::new(16, 16);
+
+        batch.add(
+            Rect::origin(16., 16.),
+            Rect::new(0., 0., 8., 8.),
+            Rgba::WHITE,
+            1.0,
+            Repeat::default(),
+        );
+        batch.add(
+            Rect::new(0., 0., 8., 8.),
+            Rect::new(8., 8., 16., 16.),
+            Rgba::WHITE,
+            1.0,
+            Repeat::default(),
+        );
+        let (verts, indices) = batch.geometry();
+
+        assert_eq!(verts.len(), 8);
+        assert_eq!(indices, vec![0, 1, 2, 0, 3, 2, 4, 5, 6, 4, 7, 6]);
+
+        #[allow(deprecated)]
+        let expanded = batch.vertices();
+
+        assert_eq!(expanded.len(), indices.len());
+        assert_eq!(expanded[4].position, verts[3].position);
+    }
+}
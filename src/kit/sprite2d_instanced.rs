@@ -0,0 +1,325 @@
+#![deny(clippy::all, clippy::use_self)]
+#![allow(clippy::new_without_default)]
+
+//! An instanced alternative to [`sprite2d`](crate::kit::sprite2d), for large
+//! numbers of sprites.
+//!
+//! All sprites share a single quad, and only one [`Instance`] per sprite is
+//! built and uploaded, instead of four vertices.
+
+use crate::core;
+use crate::core::{
+    Binding, BindingType, Rect, Rgba, Set, ShaderStage, StepMode, Vertex, VertexFormat,
+};
+
+use crate::math::*;
+
+use crate::kit;
+use crate::kit::{Model, Repeat, Rgba8};
+
+pub use crate::kit::sprite2d::Uniforms;
+
+///////////////////////////////////////////////////////////////////////////
+// Instance
+///////////////////////////////////////////////////////////////////////////
+
+/// The per-instance data of a sprite.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct Instance {
+    /// Texture coordinates of the sprite, as `(u1, v1, u2, v2)`.
+    src: Vector4<f32>,
+    /// Position of the sprite, as `(x1, y1, x2, y2)`.
+    dst: Vector4<f32>,
+    color: Rgba8,
+    opacity: f32,
+    transform: Matrix4<f32>,
+}
+
+impl Vertex for Instance {
+    const FORMATS: &'static [VertexFormat] = &[
+        VertexFormat::Float4,
+        VertexFormat::Float4,
+        VertexFormat::UByte4,
+        VertexFormat::Float,
+        // Instance transform, one column at a time.
+        VertexFormat::Float4,
+        VertexFormat::Float4,
+        VertexFormat::Float4,
+        VertexFormat::Float4,
+    ];
+}
+
+/// A corner of the quad shared by all sprites.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct Corner(Vector2<f32>);
+
+impl Vertex for Corner {
+    const FORMATS: &'static [VertexFormat] = &[VertexFormat::Float2];
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Pipeline
+///////////////////////////////////////////////////////////////////////////
+
+pub struct Pipeline {
+    pipeline: core::Pipeline,
+    bindings: core::BindingGroup,
+    buf: core::UniformBuffer,
+    quad: core::Mesh<Corner>,
+    width: u32,
+    height: u32,
+    ortho: Matrix4<f32>,
+    model: Model,
+}
+
+impl Pipeline {
+    pub fn binding(
+        &self,
+        renderer: &core::Renderer,
+        texture: &core::Texture,
+        sampler: &core::Sampler,
+    ) -> core::BindingGroup {
+        renderer
+            .device
+            .create_binding_group(&self.pipeline.layout.sets[2], &[texture, sampler])
+    }
+
+    /// Create a [`Model`] with the given transforms, to draw with
+    /// [`Model::bind`] instead of the pipeline's identity transform.
+    pub fn model(&self, renderer: &core::Renderer, transforms: &[Matrix4<f32>]) -> Model {
+        Model::new(&self.pipeline.layout.sets[1], transforms, &renderer.device)
+    }
+
+    /// Draw all sprite instances of a finished [`Batch`] with the given
+    /// texture binding. The pipeline must be set on the pass.
+    pub fn draw(
+        &self,
+        instances: &core::VertexBuffer<Instance>,
+        binding: &core::BindingGroup,
+        pass: &mut core::Pass,
+    ) {
+        pass.set_binding(binding, &[]);
+        pass.draw_mesh_instanced(&self.quad, instances);
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////
+
+impl<'a> core::AbstractPipeline<'a> for Pipeline {
+    type PrepareContext = Matrix4<f32>;
+    type Uniforms = self::Uniforms;
+
+    fn description() -> core::PipelineDescription<'a> {
+        core::PipelineDescription {
+            vertex_layout: Corner::FORMATS,
+            vertex_stride: None,
+            vertex_slots: &[core::VertexSlot {
+                step_mode: StepMode::Instance,
+                formats: Instance::FORMATS,
+                stride: None,
+            }],
+            pipeline_layout: &[
+                Set(&[Binding {
+                    binding: BindingType::UniformBuffer,
+                    stage: ShaderStage::Vertex,
+                }]),
+                Set(&[Binding {
+                    binding: BindingType::UniformBufferDynamic,
+                    stage: ShaderStage::Vertex,
+                }]),
+                Set(&[
+                    Binding {
+                        binding: BindingType::SampledTexture,
+                        stage: ShaderStage::Fragment,
+                    },
+                    Binding {
+                        binding: BindingType::Sampler,
+                        stage: ShaderStage::Fragment,
+                    },
+                ]),
+            ],
+            // TODO: Use `env("CARGO_MANIFEST_DIR")`
+            vertex_shader: include_bytes!("data/sprite_instanced.vert.spv"),
+            fragment_shader: include_bytes!("data/sprite.frag.spv"),
+            format: core::SwapChain::FORMAT,
+            index_format: core::IndexFormat::Uint16,
+            targets: &[],
+            depth_stencil: None,
+            primitive: core::PrimitiveState::default(),
+            entry_points: core::EntryPoints::MAIN,
+        }
+    }
+
+    fn setup(pipeline: core::Pipeline, dev: &core::Device, width: u32, height: u32) -> Self {
+        let ortho = kit::ortho(width, height);
+        let transform = Matrix4::identity();
+        let model = Model::new(&pipeline.layout.sets[1], &[Matrix4::identity()], dev);
+        let buf = dev.create_uniform_buffer(&[self::Uniforms { ortho, transform }]);
+        let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&buf]);
+        let quad = core::Mesh {
+            vertices: dev.create_buffer(&[
+                Corner(Vector2::new(0., 0.)),
+                Corner(Vector2::new(1., 0.)),
+                Corner(Vector2::new(1., 1.)),
+                Corner(Vector2::new(0., 1.)),
+            ]),
+            indices: dev.create_index(&[0, 1, 2, 0, 3, 2]),
+        };
+
+        Self {
+            pipeline,
+            buf,
+            bindings,
+            quad,
+            model,
+            ortho,
+            width,
+            height,
+        }
+    }
+
+    fn resize(&mut self, w: u32, h: u32) {
+        self.width = w;
+        self.height = h;
+        self.ortho = kit::ortho(w, h);
+    }
+
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn apply(&self, pass: &mut core::Pass) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_binding(&self.bindings, &[]);
+        self.model.bind(pass, 0);
+    }
+
+    fn prepare(
+        &'a self,
+        transform: Matrix4<f32>,
+    ) -> Option<(&'a core::UniformBuffer, Vec<self::Uniforms>)> {
+        Some((
+            &self.buf,
+            vec![self::Uniforms {
+                transform,
+                ortho: self.ortho,
+            }],
+        ))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// Batch
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A batch of sprites, with the same API as [`sprite2d::Batch`](crate::kit::sprite2d::Batch).
+/// Sprites can additionally be given their own transform.
+#[derive(Clone, Debug)]
+pub struct Batch {
+    pub w: u32,
+    pub h: u32,
+    pub size: usize,
+
+    items: Vec<Instance>,
+}
+
+impl Batch {
+    pub fn new(w: u32, h: u32) -> Self {
+        Self {
+            w,
+            h,
+            items: Vec::new(),
+            size: 0,
+        }
+    }
+
+    pub fn singleton(
+        w: u32,
+        h: u32,
+        src: Rect<f32>,
+        dst: Rect<f32>,
+        rgba: Rgba,
+        opa: f32,
+        rep: Repeat,
+    ) -> Self {
+        let mut view = Self::new(w, h);
+        view.add(src, dst, rgba, opa, rep);
+        view
+    }
+
+    pub fn add(&mut self, src: Rect<f32>, dst: Rect<f32>, rgba: Rgba, opacity: f32, rep: Repeat) {
+        self.add_transformed(src, dst, rgba, opacity, rep, Matrix4::identity());
+    }
+
+    /// Add a sprite which is transformed by `transform`, before the
+    /// pipeline's own transforms.
+    pub fn add_transformed(
+        &mut self,
+        src: Rect<f32>,
+        dst: Rect<f32>,
+        rgba: Rgba,
+        opacity: f32,
+        rep: Repeat,
+        transform: Matrix4<f32>,
+    ) {
+        if rep != Repeat::default() {
+            assert!(
+                src == Rect::origin(self.w as f32, self.h as f32),
+                "using texture repeat is only valid when using the entire {}x{} texture",
+                self.w,
+                self.h
+            );
+        }
+        // Relative texture coordinates
+        let rx1: f32 = src.x1 / self.w as f32;
+        let ry1: f32 = src.y1 / self.h as f32;
+        let rx2: f32 = src.x2 / self.w as f32;
+        let ry2: f32 = src.y2 / self.h as f32;
+
+        self.items.push(Instance {
+            src: Vector4::new(rx1 * rep.x, ry1 * rep.y, rx2 * rep.x, ry2 * rep.y),
+            dst: Vector4::new(dst.x1, dst.y1, dst.x2, dst.y2),
+            color: rgba.into(),
+            opacity,
+            transform,
+        });
+        self.size += 1;
+    }
+
+    /// The instances of all sprites in the batch.
+    pub fn instances(&self) -> &[Instance] {
+        self.items.as_slice()
+    }
+
+    pub fn finish(self, r: &core::Renderer) -> core::VertexBuffer<Instance> {
+        r.device.create_buffer(self.instances())
+    }
+
+    /// Upload the batch into an existing instance buffer, eg. the one of a
+    /// previous frame. The buffer is only reallocated if the batch doesn't fit.
+    pub fn upload(
+        &self,
+        r: &mut core::Renderer,
+        buf: &mut core::VertexBuffer<Instance>,
+        f: &mut core::Frame,
+    ) {
+        r.update_vertex_buffer(buf, self.instances(), f);
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.size = 0;
+    }
+
+    pub fn offset(&mut self, x: f32, y: f32) {
+        for i in self.items.iter_mut() {
+            i.dst = Vector4::new(i.dst.x + x, i.dst.y + y, i.dst.z + x, i.dst.w + y);
+        }
+    }
+}